@@ -0,0 +1,32 @@
+// generates C header from src/ffi.rs and compiles C test harness against it,
+// the harness is linked to unit tests only
+fn main() {
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    println!("cargo:rerun-if-changed=ffi/harness.c");
+
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+
+    let mut config = cbindgen::Config::default();
+    config.language = cbindgen::Language::C;
+    config.header = Some(String::from("/* Generated from src/ffi.rs, do not edit by hand. */"));
+    config.include_guard = Some(String::from("ADVENT2018_H"));
+    config.no_includes = true;
+    config.sys_includes = vec![String::from("stddef.h"), String::from("stdint.h")];
+    config.cpp_compat = true;
+    config.usize_is_size_t = true;
+    config.documentation_style = cbindgen::DocumentationStyle::C;
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src("src/ffi.rs")
+        .generate()
+        .expect("can't generate C header from src/ffi.rs")
+        .write_to_file(format!("{}/advent2018.h", out_dir));
+
+    cc::Build::new()
+        .file("ffi/harness.c")
+        .include(&out_dir)
+        .cargo_metadata(false)
+        .compile("advent2018_harness");
+
+    println!("cargo:rustc-link-search=native={}", out_dir);
+}
@@ -0,0 +1,80 @@
+/* Generated from src/ffi.rs, do not edit by hand. */
+
+#ifndef ADVENT2018_H
+#define ADVENT2018_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+/*
+ Answer is written to out_buf.
+ */
+#define ADVENT2018_OK 0
+
+/*
+ One of the pointers is NULL.
+ */
+#define ADVENT2018_ERR_NULL_POINTER 1
+
+/*
+ Input is not valid UTF-8.
+ */
+#define ADVENT2018_ERR_INVALID_UTF8 2
+
+/*
+ Day has no solution.
+ */
+#define ADVENT2018_ERR_UNKNOWN_DAY 3
+
+/*
+ Part is neither 1 nor 2.
+ */
+#define ADVENT2018_ERR_UNKNOWN_PART 4
+
+/*
+ Solver gives no answer for this part.
+ */
+#define ADVENT2018_ERR_NOT_SOLVED 5
+
+/*
+ `*out_len` receives required capacity.
+ */
+#define ADVENT2018_ERR_BUFFER_TOO_SMALL 6
+
+/*
+ Solver panicked, most likely on malformed input.
+ */
+#define ADVENT2018_ERR_PANIC 7
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/*
+ Version of the library as NUL-terminated string with static lifetime.
+ */
+const char *advent2018_version(void);
+
+/*
+ Solves `part` (1 or 2) of `day` for `input_len` bytes of UTF-8 input and
+ writes NUL-terminated answer to `out_buf`. Returns one of the status codes.
+
+ # Safety
+
+ `input_ptr` must point to `input_len` readable bytes, it may be NULL when
+ `input_len` is 0. `out_len` must point to capacity of `out_buf`, which in
+ turn must point to that many writable bytes. On success `*out_len` receives
+ answer length without the terminating NUL.
+ */
+int32_t advent2018_solve(uint32_t day,
+                         uint32_t part,
+                         const uint8_t *input_ptr,
+                         size_t input_len,
+                         uint8_t *out_buf,
+                         size_t *out_len);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* ADVENT2018_H */
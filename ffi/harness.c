@@ -0,0 +1,78 @@
+#include <stdio.h>
+#include <string.h>
+
+#include "advent2018.h"
+
+static int failures = 0;
+
+#define CHECK(cond) do { \
+    if (!(cond)) { \
+        fprintf(stderr, "harness.c:%d: check failed: %s\n", __LINE__, #cond); \
+        failures++; \
+    } \
+} while (0)
+
+static int32_t solve_str(uint32_t day, uint32_t part, const char *input,
+                         char *out, size_t *out_len) {
+    return advent2018_solve(day, part, (const uint8_t *)input, strlen(input),
+                            (uint8_t *)out, out_len);
+}
+
+static void test_version(void) {
+    const char *version = advent2018_version();
+    CHECK(version != NULL);
+    CHECK(strlen(version) > 0);
+}
+
+static void test_answers(void) {
+    char out[64];
+    size_t out_len = sizeof(out);
+
+    CHECK(solve_str(1, 1, "+1\n-2\n+3\n+1", out, &out_len) == ADVENT2018_OK);
+    CHECK(out_len == 1);
+    CHECK(strcmp(out, "3") == 0);
+
+    out_len = sizeof(out);
+    CHECK(solve_str(5, 1, "dabAcCaCBAcCcaDA", out, &out_len) == ADVENT2018_OK);
+    CHECK(strcmp(out, "10") == 0);
+
+    /* NULL is the empty input as long as its length is 0 */
+    out_len = sizeof(out);
+    CHECK(advent2018_solve(1, 1, NULL, 0, (uint8_t *)out, &out_len) == ADVENT2018_OK);
+    CHECK(strcmp(out, "0") == 0);
+}
+
+static void test_errors(void) {
+    char out[64];
+    size_t out_len = sizeof(out);
+
+    CHECK(solve_str(12, 1, "", out, &out_len) == ADVENT2018_ERR_UNKNOWN_DAY);
+    CHECK(solve_str(1, 3, "+1", out, &out_len) == ADVENT2018_ERR_UNKNOWN_PART);
+    CHECK(solve_str(13, 2, "->-<-", out, &out_len) == ADVENT2018_ERR_NOT_SOLVED);
+    CHECK(solve_str(13, 1, "/>-\\\n|  |\n\\--/", out, &out_len) == ADVENT2018_ERR_NOT_SOLVED);
+    CHECK(advent2018_solve(1, 1, NULL, 1, (uint8_t *)out, &out_len) == ADVENT2018_ERR_NULL_POINTER);
+    CHECK(advent2018_solve(1, 1, (const uint8_t *)"+1", 2, NULL, &out_len) == ADVENT2018_ERR_NULL_POINTER);
+
+    const uint8_t invalid[] = { 0xff, 0xfe };
+    CHECK(advent2018_solve(1, 1, invalid, sizeof(invalid), (uint8_t *)out, &out_len)
+          == ADVENT2018_ERR_INVALID_UTF8);
+
+    out_len = 2;
+    CHECK(solve_str(5, 1, "dabAcCaCBAcCcaDA", out, &out_len) == ADVENT2018_ERR_BUFFER_TOO_SMALL);
+    CHECK(out_len == 3);
+}
+
+static void test_panic_is_contained(void) {
+    char out[64];
+    size_t out_len = sizeof(out);
+
+    CHECK(solve_str(8, 1, "not a tree", out, &out_len) == ADVENT2018_ERR_PANIC);
+}
+
+int advent2018_harness_run(void) {
+    test_version();
+    test_answers();
+    test_errors();
+    test_panic_is_contained();
+    return failures;
+}
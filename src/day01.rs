@@ -1,37 +1,243 @@
 use std::collections::HashSet;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read};
+use std::iter::FromIterator;
+use itertools::Itertools;
+use crate::solution::{Solution, Trace};
 
 macro_rules! input_iterator {
     ($input: expr) => {
         $input
         .lines()
-        .map(|num| num.parse::<i32>().unwrap())
+        .enumerate()
+        .map(|(i, raw)| parse_change(i + 1, raw))
     };
 }
 
-pub fn calculate_freq(input: &str) -> i32 {
+#[derive(Debug)]
+pub enum InputError {
+    Io(io::Error),
+    // line number is one-based
+    BadChange { line: usize, content: String }
+}
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputError::Io(err) => write!(f, "can't read changes: {}", err),
+            InputError::BadChange { line, content } =>
+                write!(f, "line {}: '{}' is not a frequency change", line, content)
+        }
+    }
+}
+
+fn parse_change(line: usize, raw: &str) -> Result<i32, InputError> {
+    raw.trim().parse().map_err(|_| InputError::BadChange { line, content: raw.to_string() })
+}
+
+pub fn calculate_freq(input: &str) -> Result<i32, InputError> {
     input_iterator!(input)
     .sum()
 }
 
-pub fn find_first_duplicate(input: &str) -> i32 {
-    let iterator = 
-        input_iterator!(input)
-        .cycle()
-        .scan(0, |accum, next| {
-            *accum += next;
+pub fn find_first_duplicate(input: &str) -> Result<Option<i32>, InputError> {
+    Ok(find_first_repeat(input)?.map(|repeat| repeat.frequency))
+}
+
+pub fn find_first_repeat(input: &str) -> Result<Option<Repeat>, InputError> {
+    let device: FrequencyDevice = input_iterator!(input).collect::<Result<_, _>>()?;
+    Ok(device.first_repeat())
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Repeat {
+    pub frequency: i32,
+    // zero-based pass over the whole list of changes
+    pub iteration: usize,
+    // zero-based index of the change which leads to the repeated frequency
+    pub position: usize
+}
+impl Repeat {
+    // step is the amount of changes applied since the start
+    fn at_step(step: usize, length: usize, frequency: i64) -> Self {
+        Repeat {
+            frequency: frequency as i32,
+            iteration: (step - 1) / length,
+            position: (step - 1) % length
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Extremum {
+    pub frequency: i32,
+    // amount of changes applied when the frequency is reached
+    pub step: usize
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Summary {
+    // frequency after one pass over the changes
+    pub final_frequency: i32,
+    pub drift: i32,
+    // extremes within one pass, later passes only shift them by the drift
+    pub min: Extremum,
+    pub max: Extremum,
+    // None when frequency never repeats
+    pub distinct_before_repeat: Option<usize>
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct FrequencyDevice {
+    start: i32,
+    changes: Vec<i32>
+}
+impl FrequencyDevice {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn starting_at(mut self, frequency: i32) -> Self {
+        self.start = frequency;
+        self
+    }
+
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, InputError> {
+        let mut changes = Vec::new();
+        for (i, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line.map_err(InputError::Io)?;
+            changes.push(parse_change(i + 1, &line)?);
+        }
+        Ok(Self { start: 0, changes })
+    }
+
+    pub fn summary(&self) -> Summary {
+        let partial = self.partial_sums();
+        let drift = partial[partial.len() - 1];
+
+        let extremum = |(step, freq): (usize, &i64)| Extremum {
+            frequency: (i64::from(self.start) + freq) as i32,
+            step
+        };
+        // ties resolve to the earliest step
+        let min =
+            partial.iter().enumerate()
+            .min_by_key(|(step, freq)| (**freq, *step))
+            .map(extremum)
+            .unwrap();
+        let max =
+            partial.iter().enumerate()
+            .max_by_key(|(step, freq)| (**freq, std::cmp::Reverse(*step)))
+            .map(extremum)
+            .unwrap();
+
+        let distinct_before_repeat =
+            self.first_repeat()
+            .map(|repeat| repeat.iteration * self.changes.len() + repeat.position + 1);
+
+        Summary {
+            final_frequency: (i64::from(self.start) + drift) as i32,
+            drift: drift as i32,
+            min,
+            max,
+            distinct_before_repeat
+        }
+    }
+
+    // every pass reaches the same partial sums shifted by the drift of the whole
+    // pass, so after the first pass a frequency may only repeat one reached by
+    // a partial sum from the same residue class modulo the drift
+    pub fn first_repeat(&self) -> Option<Repeat> {
+        let length = self.changes.len();
+        if length == 0 { return None; }
+
+        let partial = self.partial_sums();
+        let drift = partial[length];
+
+        let mut seen = HashSet::new();
+        for (step, freq) in partial.iter().enumerate() {
+            if !seen.insert(*freq) {
+                return Some(Repeat::at_step(step, length, i64::from(self.start) + *freq));
+            }
+        }
+
+        // no repeats within the first pass means the drift isn't zero
+        // and partial sums of a single pass are all distinct
+        let partial = &partial[..length];
+        let shift = drift.abs();
+
+        let mut first_repeat: Option<(usize, i64)> = None;
+        for class in partial
+            .iter()
+            .enumerate()
+            .sorted_by_key(|(_i, freq)| (freq.rem_euclid(shift), **freq))
+            .group_by(|(_i, freq)| freq.rem_euclid(shift))
+            .into_iter()
+        {
+            // sum, which is next in the direction of drift, is the closest
+            // one to be reached again, so only neighbours need to be checked
+            let class: Vec<_> = class.1.collect();
+            for pair in class.windows(2) {
+                let ((i_low, low), (i_high, high)) = (pair[0], pair[1]);
+                let iterations = ((high - low) / shift) as usize;
+                let (step, freq) = if drift > 0 {
+                    (iterations * length + i_low, *high)
+                } else {
+                    (iterations * length + i_high, *low)
+                };
+                if first_repeat.is_none_or(|(first_step, _)| step < first_step) {
+                    first_repeat = Some((step, freq));
+                }
+            }
+        }
+
+        first_repeat.map(|(step, freq)| Repeat::at_step(step, length, i64::from(self.start) + freq))
+    }
+
+    // partial[i] is the shift from start after first i changes, so it has one
+    // more element than there are changes
+    fn partial_sums(&self) -> Vec<i64> {
+        std::iter::once(0)
+        .chain(self.changes.iter().scan(0, |accum, change| {
+            *accum += i64::from(*change);
             Some(*accum)
-        });
+        }))
+        .collect()
+    }
+}
+impl FromIterator<i32> for FrequencyDevice {
+    fn from_iter<I: IntoIterator<Item = i32>>(changes: I) -> Self {
+        Self { start: 0, changes: changes.into_iter().collect() }
+    }
+}
+
+pub struct Solver;
+impl Solution for Solver {
+    fn part_1(&self, input: &str) -> Option<String> {
+        Some(calculate_freq(input).unwrap().to_string())
+    }
 
-    let mut hash_set = HashSet::new();
+    fn part_2(&self, input: &str) -> Option<String> {
+        // no repeat is not an answer, explain tells why there is none
+        find_first_duplicate(input).unwrap().map(|freq| freq.to_string())
+    }
 
-    for r in iterator {
-        if hash_set.get(&r).is_some() {
-            return r
-        } else {
-            hash_set.insert(r);
+    fn explain(&self, input: &str, trace: &mut Trace) {
+        let device: FrequencyDevice = input_iterator!(input).collect::<Result<_, _>>().unwrap();
+        let summary = device.summary();
+        trace.record("changes", input.lines().count());
+        trace.record("drift per cycle", summary.drift);
+        trace.record("lowest frequency", format!("{} after {} changes", summary.min.frequency, summary.min.step));
+        trace.record("highest frequency", format!("{} after {} changes", summary.max.frequency, summary.max.step));
+        match device.first_repeat() {
+            Some(repeat) => {
+                trace.record("first repeated frequency", repeat.frequency);
+                trace.record("reached at iteration", repeat.iteration);
+                trace.record("reached at position", repeat.position);
+                trace.record("distinct frequencies before it", summary.distinct_before_repeat.unwrap());
+            },
+            None => trace.record("first repeated frequency", "none, frequency never repeats")
         }
     }
-    -1
 }
 
 #[test]
@@ -42,6 +248,62 @@ fn test_example() {
 +3
 +1";
 
-    assert_eq!(calculate_freq(test_input), 3);
-    assert_eq!(find_first_duplicate(test_input), 2);
-}
\ No newline at end of file
+    assert_eq!(calculate_freq(test_input).unwrap(), 3);
+    assert_eq!(find_first_duplicate(test_input).unwrap(), Some(2));
+    assert_eq!(find_first_repeat(test_input).unwrap(), Some(Repeat { frequency: 2, iteration: 1, position: 1 }));
+}
+
+#[test]
+fn test_first_repeat() {
+    assert_eq!(find_first_duplicate("+1\n-1").unwrap(), Some(0));
+    assert_eq!(find_first_duplicate("+3\n+3\n+4\n-2\n-4").unwrap(), Some(10));
+    assert_eq!(find_first_duplicate("-6\n+3\n+8\n+5\n-6").unwrap(), Some(5));
+    assert_eq!(find_first_duplicate("+7\n+7\n-2\n-7\n-4").unwrap(), Some(14));
+    assert_eq!(find_first_duplicate("-7\n-7\n+2\n+7\n+4").unwrap(), Some(-14));
+    assert_eq!(find_first_duplicate("+1\n+2\n-1").unwrap(), Some(3));
+    assert_eq!(
+        find_first_repeat("+2\n-1\n+1").unwrap(),
+        Some(Repeat { frequency: 2, iteration: 0, position: 2 })
+    );
+}
+
+#[test]
+fn test_no_repeat() {
+    assert_eq!(find_first_duplicate("+1\n+2\n+3").unwrap(), None);
+    assert_eq!(find_first_duplicate("+2\n+3\n-1").unwrap(), None);
+    assert_eq!(find_first_duplicate("").unwrap(), None);
+}
+
+#[test]
+fn test_bad_change() {
+    match calculate_freq("+1\n+x\n-1") {
+        Err(InputError::BadChange { line, content }) => {
+            assert_eq!(line, 2);
+            assert_eq!(content, "+x");
+        },
+        other => panic!("unexpected result {:?}", other)
+    }
+}
+
+#[test]
+fn test_device_summary() {
+    let device = FrequencyDevice::from_reader("+1\n-2\n+3\n+1\n".as_bytes()).unwrap();
+
+    let expected = Summary {
+        final_frequency: 3,
+        drift: 3,
+        min: Extremum { frequency: -1, step: 2 },
+        max: Extremum { frequency: 3, step: 4 },
+        distinct_before_repeat: Some(6)
+    };
+
+    assert_eq!(device.summary(), expected);
+
+    let shifted = device.starting_at(10).summary();
+    assert_eq!(shifted.final_frequency, 13);
+    assert_eq!(shifted.min, Extremum { frequency: 9, step: 2 });
+
+    let device: FrequencyDevice = vec![1, 2, 3].into_iter().collect();
+    assert_eq!(device.summary().distinct_before_repeat, None);
+    assert_eq!(device.first_repeat(), None);
+}
@@ -1,53 +1,346 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use itertools::Itertools;
+use crate::solution::Solution;
 
-struct Id {
-    two: bool,
-    three: bool
+#[derive(Debug, PartialEq)]
+pub struct Id {
+    // multiplicity -> amount of distinct letters appearing exactly that many times
+    histogram: BTreeMap<usize, usize>
 }
 impl Id {
-    fn parse_str(raw_str: &str) -> Self {
+    // letters are unicode scalar values, so any id works, not only ascii
+    pub fn parse_str(raw_str: &str) -> Self {
         let mut hm = HashMap::new();
         for ch in raw_str.chars() {
-            if let Some(amount) = hm.get_mut(&ch) {
-                *amount += 1;
-            } else {
-                hm.insert(ch, 1);
-            }
+            *hm.entry(ch).or_insert(0) += 1;
         }
-        let two = hm.values().any(|val| *val == 2);
-        let three = hm.values().any(|val| *val == 3);
 
-        Self { two, three }
+        let mut histogram = BTreeMap::new();
+        for amount in hm.values() {
+            *histogram.entry(*amount).or_insert(0) += 1;
+        }
+
+        Self { histogram }
+    }
+
+    pub fn histogram(&self) -> &BTreeMap<usize, usize> {
+        &self.histogram
+    }
+
+    pub fn has_multiplicity(&self, multiplicity: usize) -> bool {
+        self.histogram.contains_key(&multiplicity)
+    }
+}
+
+// for every requested multiplicity counts ids which have at least one letter
+// appearing exactly that many times, pairs follow the request order and
+// repeated multiplicities get an entry each
+pub fn count_multiplicities(input: &str, multiplicities: &[usize]) -> Vec<(usize, usize)> {
+    let mut breakdown: Vec<(usize, usize)> =
+        multiplicities.iter().map(|m| (*m, 0)).collect();
+
+    for id in input.lines().map(Id::parse_str) {
+        for (multiplicity, count) in breakdown.iter_mut() {
+            if id.has_multiplicity(*multiplicity) { *count += 1; }
+        }
     }
+
+    breakdown
 }
 
 pub fn calculate_checksum(input: &str) -> usize {
-    let ids: Vec<Id> =
-        input
-        .lines()
-        .map(Id::parse_str)
+    count_multiplicities(input, &[2, 3]).iter().map(|(_, count)| count).product()
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct BoxPair<'a> {
+    pub first: &'a str,
+    pub second: &'a str,
+    // char positions where ids differ
+    pub differences: Vec<usize>
+}
+impl<'a> BoxPair<'a> {
+    // letters which both ids have at the same positions
+    pub fn common_letters(&self) -> String {
+        self.first
+        .chars()
+        .enumerate()
+        .filter(|(i, _ch)| !self.differences.contains(i))
+        .map(|(_i, ch)| ch)
+        .collect()
+    }
+}
+
+const HASH_BASE: u64 = 1_000_003;
+
+// ids which differ in exactly one position have equal hashes once that
+// position is masked out, so every id is hashed once per position
+pub fn find_correct_box(input: &str) -> Option<String> {
+    let ids: Vec<Vec<char>> = input.lines().map(|id| id.chars().collect()).collect();
+    let max_len = ids.iter().map(Vec::len).max().unwrap_or(0);
+    let powers: Vec<u64> =
+        std::iter::successors(Some(1u64), |p| Some(p.wrapping_mul(HASH_BASE)))
+        .take(max_len)
         .collect();
-    let twos = ids.iter().filter(|id| id.two).count();
-    let threes = ids.iter().filter(|id| id.three).count();
-    twos * threes
-}
-
-pub fn find_correct_box<'a>(input: &str) -> String {
-    let ids: Vec<_> = input.lines().collect();
-    for (i, str1) in ids.iter().enumerate() {
-        for str2 in ids.iter().skip(i+1) {
-            let mut differences_amount = 0;
-            let mut possible_result = Vec::new();
-            for (ch1, ch2) in str1.chars().zip(str2.chars()) {
-                if ch1 != ch2 { differences_amount += 1 }
-                else { possible_result.push(ch1) }
+
+    let mut buckets: HashMap<(usize, usize, u64), Vec<usize>> = HashMap::new();
+    // (first id, second id, differing position)
+    let mut found: Option<(usize, usize, usize)> = None;
+
+    for (j, id) in ids.iter().enumerate() {
+        let len = id.len();
+        let hash =
+            id.iter()
+            .fold(0u64, |acc, ch| acc.wrapping_mul(HASH_BASE).wrapping_add(*ch as u64));
+
+        for (pos, ch) in id.iter().enumerate() {
+            let masked = hash.wrapping_sub((*ch as u64).wrapping_mul(powers[len - 1 - pos]));
+            let bucket = buckets.entry((pos, len, masked)).or_default();
+            for &i in bucket.iter() {
+                // hashes may collide, so candidates are compared for real
+                let other = &ids[i];
+                let is_pair =
+                    other[pos] != *ch &&
+                    other.iter().zip(id).enumerate().all(|(k, (a, b))| k == pos || a == b);
+                if is_pair && found.is_none_or(|(fi, fj, _)| (i, j) < (fi, fj)) {
+                    found = Some((i, j, pos));
+                }
             }
-            if differences_amount == 1 {
-                return possible_result.iter().collect();
+            bucket.push(j);
+        }
+    }
+
+    found.map(|(i, _j, pos)| {
+        ids[i].iter().enumerate().filter(|(k, _)| *k != pos).map(|(_k, ch)| ch).collect()
+    })
+}
+
+// any two ids within distance k share at least one of k+1 blocks they are
+// split to, so only ids with some equal block need to be compared
+pub fn find_boxes_within(input: &str, k: usize) -> Vec<BoxPair<'_>> {
+    let raw_ids: Vec<&str> = input.lines().collect();
+    let ids: Vec<Vec<char>> = raw_ids.iter().map(|id| id.chars().collect()).collect();
+
+    let mut buckets: HashMap<(usize, usize, &[char]), Vec<usize>> = HashMap::new();
+    for (i, id) in ids.iter().enumerate() {
+        let len = id.len();
+        for block in 0..=k {
+            let from = len * block / (k + 1);
+            let to = len * (block + 1) / (k + 1);
+            buckets.entry((len, block, &id[from..to])).or_default().push(i);
+        }
+    }
+
+    let mut candidates = HashSet::new();
+    for bucket in buckets.values() {
+        for (n, &i) in bucket.iter().enumerate() {
+            for &j in bucket.iter().skip(n + 1) {
+                candidates.insert((i, j));
+            }
+        }
+    }
+
+    candidates
+        .into_iter()
+        .sorted()
+        .filter_map(|(i, j)| {
+            let differences: Vec<usize> =
+                ids[i].iter()
+                .zip(&ids[j])
+                .enumerate()
+                .filter(|(_pos, (a, b))| a != b)
+                .map(|(pos, _)| pos)
+                .collect();
+            if differences.len() <= k {
+                Some(BoxPair { first: raw_ids[i], second: raw_ids[j], differences })
+            } else {
+                None
             }
+        })
+        .collect()
+}
+
+// positions past the end of the shorter id count as differences
+pub fn hamming_distance(a: &str, b: &str) -> usize {
+    let mut a = a.chars();
+    let mut b = b.chars();
+    let mut distance = 0;
+    loop {
+        match (a.next(), b.next()) {
+            (None, None) => return distance,
+            (Some(ch1), Some(ch2)) if ch1 == ch2 => (),
+            _ => distance += 1
         }
     }
-    return String::new();
+}
+
+#[derive(Debug)]
+struct BkNode<'a> {
+    id: &'a str,
+    // distance to child -> index of child node
+    children: HashMap<usize, usize>
+}
+
+// metric tree over hamming distance, triangle inequality lets queries
+// skip subtrees which can't contain anything close enough
+#[derive(Debug, Default)]
+pub struct BkTree<'a> {
+    nodes: Vec<BkNode<'a>>
+}
+impl<'a> BkTree<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_input(input: &'a str) -> Self {
+        let mut tree = Self::new();
+        for id in input.lines() {
+            tree.insert(id);
+        }
+        tree
+    }
+
+    pub fn insert(&mut self, id: &'a str) {
+        let new_index = self.nodes.len();
+        self.nodes.push(BkNode { id, children: HashMap::new() });
+        if new_index == 0 { return; }
+
+        let mut current = 0;
+        loop {
+            let distance = hamming_distance(self.nodes[current].id, id);
+            match self.nodes[current].children.get(&distance) {
+                Some(child) => current = *child,
+                None => {
+                    self.nodes[current].children.insert(distance, new_index);
+                    return;
+                }
+            }
+        }
+    }
+
+    // all ids at distance k or less, closest first
+    pub fn within(&self, id: &str, k: usize) -> Vec<(&'a str, usize)> {
+        let mut found = Vec::new();
+        let mut stack = self.root();
+
+        while let Some(index) = stack.pop() {
+            let distance = hamming_distance(self.nodes[index].id, id);
+            if distance <= k {
+                found.push((self.nodes[index].id, distance));
+            }
+            self.push_children(index, distance, k, &mut stack);
+        }
+
+        found.sort_by_key(|(node_id, distance)| (*distance, *node_id));
+        found
+    }
+
+    // up to `limit` ids nearest to the given one, closest first
+    pub fn closest_to(&self, id: &str, limit: usize) -> Vec<(&'a str, usize)> {
+        let mut found: Vec<(&'a str, usize)> = Vec::new();
+        if limit == 0 { return found; }
+        let mut stack = self.root();
+
+        while let Some(index) = stack.pop() {
+            let node_id = self.nodes[index].id;
+            let distance = hamming_distance(node_id, id);
+            let pos =
+                found
+                .binary_search_by_key(&(distance, node_id), |(id, d)| (*d, *id))
+                .unwrap_or_else(|pos| pos);
+            if pos < limit {
+                found.insert(pos, (node_id, distance));
+                found.truncate(limit);
+            }
+
+            // once there are enough results only closer ids are interesting
+            let radius = if found.len() < limit { usize::MAX } else { found[limit - 1].1 };
+            self.push_children(index, distance, radius, &mut stack);
+        }
+
+        found
+    }
+
+    fn root(&self) -> Vec<usize> {
+        if self.nodes.is_empty() { vec![] } else { vec![0] }
+    }
+
+    fn push_children(&self, index: usize, distance: usize, radius: usize, stack: &mut Vec<usize>) {
+        for (child_distance, child) in &self.nodes[index].children {
+            if child_distance.abs_diff(distance) <= radius {
+                stack.push(*child);
+            }
+        }
+    }
+}
+
+// groups ids into clusters, where every two members are within hamming
+// distance k of one another. it is complete linkage: close pairs are taken
+// from the closest one and merge their clusters only if the limit still holds
+// for all the members, so the result depends on that order but never breaks it
+pub fn cluster_boxes(input: &str, k: usize) -> Vec<Vec<&str>> {
+    let ids: Vec<&str> = input.lines().collect();
+    let tree = BkTree::from_input(input);
+
+    let mut index_of: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, id) in ids.iter().enumerate() {
+        index_of.entry(id).or_default().push(i);
+    }
+
+    let mut pairs: Vec<(usize, usize, usize)> = Vec::new();
+    for (i, id) in ids.iter().enumerate() {
+        for (neighbour, distance) in tree.within(id, k) {
+            pairs.extend(index_of[neighbour].iter().filter(|&&j| j > i).map(|&j| (distance, i, j)));
+        }
+    }
+    pairs.sort_unstable();
+
+    let mut clusters: Vec<Vec<usize>> = (0..ids.len()).map(|i| vec![i]).collect();
+    let mut cluster_of: Vec<usize> = (0..ids.len()).collect();
+    for (_distance, i, j) in pairs {
+        let (into, from) = (cluster_of[i], cluster_of[j]);
+        if into == from { continue }
+
+        let fits = clusters[into]
+            .iter()
+            .all(|&a| clusters[from].iter().all(|&b| hamming_distance(ids[a], ids[b]) <= k));
+        if fits {
+            let moved = std::mem::take(&mut clusters[from]);
+            for &member in &moved {
+                cluster_of[member] = into;
+            }
+            clusters[into].extend(moved);
+        }
+    }
+
+    clusters
+        .into_iter()
+        .filter(|members| !members.is_empty())
+        .map(|members| members.into_iter().map(|i| ids[i]).sorted().collect::<Vec<_>>())
+        .sorted_by(|c1, c2| c2.len().cmp(&c1.len()).then(c1.cmp(c2)))
+        .collect()
+}
+
+pub fn render_clusters(clusters: &[Vec<&str>]) -> String {
+    let mut text = String::new();
+    for (i, cluster) in clusters.iter().enumerate() {
+        text.push_str(&format!("cluster {} ({} boxes):\n", i + 1, cluster.len()));
+        for id in cluster {
+            text.push_str(&format!("  {}\n", id));
+        }
+    }
+    text
+}
+
+pub struct Solver;
+impl Solution for Solver {
+    fn part_1(&self, input: &str) -> Option<String> {
+        Some(calculate_checksum(input).to_string())
+    }
+
+    fn part_2(&self, input: &str) -> Option<String> {
+        find_correct_box(input)
+    }
 }
 
 #[test]
@@ -61,7 +354,24 @@ aabcdd
 abcdee
 ababab";
 
-    assert_eq!(calculate_checksum(test_data), 12)
+    assert_eq!(calculate_checksum(test_data), 12);
+
+    let breakdown = count_multiplicities(test_data, &[1, 2, 3, 4]);
+    assert_eq!(breakdown, vec![(1, 6), (2, 4), (3, 3), (4, 0)]);
+
+    let breakdown = count_multiplicities(test_data, &[3, 2, 2]);
+    assert_eq!(breakdown, vec![(3, 3), (2, 4), (2, 4)]);
+}
+
+#[test]
+fn test_multiplicity_histogram() {
+    let id = Id::parse_str("bababc");
+    let expected: BTreeMap<_, _> = vec![(1, 1), (2, 1), (3, 1)].into_iter().collect();
+    assert_eq!(id.histogram(), &expected);
+
+    let unicode = Id::parse_str("ёлкаёё🎄🎄");
+    let expected: BTreeMap<_, _> = vec![(1, 3), (2, 1), (3, 1)].into_iter().collect();
+    assert_eq!(unicode.histogram(), &expected);
 }
 
 #[test]
@@ -75,5 +385,83 @@ fguij
 axcye
 wvxyz";
 
-    assert_eq!(find_correct_box(test_data), "fgij")
+    assert_eq!(find_correct_box(test_data), Some(String::from("fgij")));
+    assert_eq!(find_correct_box("abcde\nfghij"), None);
+}
+
+#[test]
+fn test_boxes_within() {
+    let test_data =
+r"abcde
+fghij
+klmno
+pqrst
+fguij
+axcye
+wvxyz";
+
+    let pairs = find_boxes_within(test_data, 1);
+    let expected = vec![BoxPair { first: "fghij", second: "fguij", differences: vec![2] }];
+    assert_eq!(pairs, expected);
+    assert_eq!(pairs[0].common_letters(), "fgij");
+
+    let pairs = find_boxes_within(test_data, 2);
+    assert_eq!(pairs.len(), 2);
+    assert_eq!(pairs[0], BoxPair { first: "abcde", second: "axcye", differences: vec![1, 3] });
+
+    assert!(find_boxes_within("ab\nabc", 1).is_empty());
+    assert_eq!(find_boxes_within("ab\ncd", 2).len(), 1);
+}
+
+#[test]
+fn test_hamming_distance() {
+    assert_eq!(hamming_distance("fghij", "fguij"), 1);
+    assert_eq!(hamming_distance("abc", "abcde"), 2);
+    assert_eq!(hamming_distance("", ""), 0);
+}
+
+#[test]
+fn test_bk_tree() {
+    let test_data =
+r"abcde
+fghij
+klmno
+pqrst
+fguij
+axcye
+wvxyz";
+
+    let tree = BkTree::from_input(test_data);
+
+    assert_eq!(tree.within("fghij", 1), vec![("fghij", 0), ("fguij", 1)]);
+    assert_eq!(tree.closest_to("abcdf", 2), vec![("abcde", 1), ("axcye", 3)]);
+    assert_eq!(tree.closest_to("zzzzz", 0), vec![]);
+    assert_eq!(BkTree::new().closest_to("abc", 3), vec![]);
+}
+
+#[test]
+fn test_clustering() {
+    let test_data =
+r"abcde
+fghij
+abcdz
+fguij
+abxdz
+wvxyz";
+
+    // abxdz is one step from abcdz, but two from abcde which joined it first
+    let clusters = cluster_boxes(test_data, 1);
+    let expected = vec![
+        vec!["abcde", "abcdz"],
+        vec!["fghij", "fguij"],
+        vec!["abxdz"],
+        vec!["wvxyz"],
+    ];
+    assert_eq!(clusters, expected);
+
+    let clusters = cluster_boxes(test_data, 2);
+    assert_eq!(clusters[0], vec!["abcde", "abcdz", "abxdz"]);
+
+    let text = render_clusters(&clusters[1..]);
+    assert_eq!(text, "cluster 1 (2 boxes):\n  fghij\n  fguij\ncluster 2 (1 boxes):\n  wvxyz\n");
 }
\ No newline at end of file
@@ -1,94 +1,404 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet};
+use crate::solution::{Solution, Trace};
 
-const FABRIC_SIZE: u16 = 1000;
+// half-open rectangle, right and bottom edges are excluded
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Bounds {
+    left: usize,
+    top: usize,
+    right: usize,
+    bottom: usize
+}
+impl Bounds {
+    fn is_empty(&self) -> bool {
+        self.left >= self.right || self.top >= self.bottom
+    }
 
-#[derive(Debug, PartialEq)]
-enum State {
-    Available,
-    Reserved(u16),
-    Overlapped(HashSet<u16>)
-}
-impl State {
-    fn i_need_it(&mut self, id: u16) {
-        match self {
-            State::Available => *self = State::Reserved(id),
-            State::Reserved(by_id) => {
-                let mut hs = HashSet::new();
-                hs.insert(*by_id);
-                hs.insert(id);
-                *self = State::Overlapped(hs)
+    fn intersection(&self, other: &Bounds) -> Bounds {
+        Bounds {
+            left: self.left.max(other.left),
+            top: self.top.max(other.top),
+            right: self.right.min(other.right),
+            bottom: self.bottom.min(other.bottom)
+        }
+    }
+
+    fn area(&self) -> usize {
+        if self.is_empty() { 0 } else { (self.right - self.left) * (self.bottom - self.top) }
+    }
+}
+
+// segment tree over compressed y coordinates, keeps length of the
+// column covered by at least one and by at least two rectangles
+struct CoverTree {
+    ys: Vec<usize>,
+    count: Vec<u32>,
+    once: Vec<usize>,
+    twice: Vec<usize>
+}
+impl CoverTree {
+    fn new(ys: Vec<usize>) -> Self {
+        let size = 4 * ys.len().max(1);
+        Self { ys, count: vec![0; size], once: vec![0; size], twice: vec![0; size] }
+    }
+
+    fn covered_once(&self) -> usize {
+        self.once[1]
+    }
+
+    fn covered_twice(&self) -> usize {
+        self.twice[1]
+    }
+
+    // adds delta to coverage of [top, bottom)
+    fn update(&mut self, top: usize, bottom: usize, delta: i32) {
+        let from = self.ys.binary_search(&top).unwrap();
+        let to = self.ys.binary_search(&bottom).unwrap();
+        if from < to {
+            self.update_node(1, 0, self.ys.len() - 1, from, to, delta);
+        }
+    }
+
+    // node covers elementary segments [l, r)
+    fn update_node(&mut self, node: usize, l: usize, r: usize, from: usize, to: usize, delta: i32) {
+        if to <= l || r <= from { return; }
+        if from <= l && r <= to {
+            self.count[node] = (self.count[node] as i32 + delta) as u32;
+        } else {
+            let mid = (l + r) / 2;
+            self.update_node(2 * node, l, mid, from, to, delta);
+            self.update_node(2 * node + 1, mid, r, from, to, delta);
+        }
+
+        let full = self.ys[r] - self.ys[l];
+        let is_leaf = r - l == 1;
+        let (children_once, children_twice) = if is_leaf {
+            (0, 0)
+        } else {
+            (self.once[2 * node] + self.once[2 * node + 1], self.twice[2 * node] + self.twice[2 * node + 1])
+        };
+        match self.count[node] {
+            0 => {
+                self.once[node] = children_once;
+                self.twice[node] = children_twice;
+            },
+            1 => {
+                self.once[node] = full;
+                self.twice[node] = children_once;
             },
-            State::Overlapped(hs) => { hs.insert(id); }
+            _ => {
+                self.once[node] = full;
+                self.twice[node] = full;
+            }
+        }
+    }
+}
+
+// sweeps vertical line across rectangles, returns area covered
+// by at least one and by at least two of them
+fn sweep_areas<'a, I: Iterator<Item = &'a Bounds>>(rectangles: I) -> (usize, usize) {
+    let rectangles: Vec<_> = rectangles.filter(|b| !b.is_empty()).collect();
+    if rectangles.is_empty() { return (0, 0); }
+
+    let mut ys: Vec<usize> = rectangles.iter().flat_map(|b| vec![b.top, b.bottom]).collect();
+    ys.sort();
+    ys.dedup();
+
+    let mut events: Vec<(usize, i32, usize, usize)> =
+        rectangles
+        .iter()
+        .flat_map(|b| vec![(b.left, 1, b.top, b.bottom), (b.right, -1, b.top, b.bottom)])
+        .collect();
+    events.sort();
+
+    let mut tree = CoverTree::new(ys);
+    let mut once = 0;
+    let mut twice = 0;
+    let mut prev_x = events[0].0;
+
+    for (x, delta, top, bottom) in events {
+        once += tree.covered_once() * (x - prev_x);
+        twice += tree.covered_twice() * (x - prev_x);
+        tree.update(top, bottom, delta);
+        prev_x = x;
+    }
+
+    (once, twice)
+}
+
+// segment tree with range addition which tracks the deepest
+// elementary segment, the leftmost one on ties
+struct DepthTree {
+    add: Vec<i32>,
+    max: Vec<(i32, usize)>
+}
+impl DepthTree {
+    fn new(segments: usize) -> Self {
+        let size = 4 * segments.max(1);
+        let mut tree = Self { add: vec![0; size], max: vec![(0, 0); size] };
+        tree.build(1, 0, segments);
+        tree
+    }
+
+    fn build(&mut self, node: usize, l: usize, r: usize) {
+        self.max[node] = (0, l);
+        if r - l > 1 {
+            let mid = (l + r) / 2;
+            self.build(2 * node, l, mid);
+            self.build(2 * node + 1, mid, r);
+        }
+    }
+
+    fn max(&self) -> (i32, usize) {
+        self.max[1]
+    }
+
+    fn update(&mut self, node: usize, l: usize, r: usize, from: usize, to: usize, delta: i32) {
+        if to <= l || r <= from { return; }
+        if from <= l && r <= to {
+            self.add[node] += delta;
+            self.max[node].0 += delta;
+            return;
+        }
+        let mid = (l + r) / 2;
+        self.update(2 * node, l, mid, from, to, delta);
+        self.update(2 * node + 1, mid, r, from, to, delta);
+
+        let (left, right) = (self.max[2 * node], self.max[2 * node + 1]);
+        let (depth, segment) = if right.0 > left.0 { right } else { left };
+        self.max[node] = (depth + self.add[node], segment);
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum State {
+    Available,
+    Reserved(u32),
+    Overlapped(BTreeSet<u32>)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Contention {
+    pub x: usize,
+    pub y: usize,
+    pub claims: Vec<u32>
+}
+
+// claim id -> overlapping claim id -> overlap area
+#[derive(Debug, PartialEq, Default)]
+pub struct OverlapGraph {
+    edges: BTreeMap<u32, BTreeMap<u32, usize>>
+}
+impl OverlapGraph {
+    // (claim id, overlap area) ordered by id
+    pub fn overlaps_of(&self, id: u32) -> Vec<(u32, usize)> {
+        self.edges
+        .get(&id)
+        .map(|neighbours| neighbours.iter().map(|(id, area)| (*id, *area)).collect())
+        .unwrap_or_default()
+    }
+
+    pub fn intact_claims(&self) -> Vec<u32> {
+        self.edges
+        .iter()
+        .filter(|(_id, neighbours)| neighbours.is_empty())
+        .map(|(id, _neighbours)| *id)
+        .collect()
+    }
+
+    // groups of claims connected by overlaps, intact claims are left out,
+    // every group is ordered and groups are ordered by their smallest id
+    pub fn components(&self) -> Vec<Vec<u32>> {
+        let mut visited = BTreeSet::new();
+        let mut components = Vec::new();
+
+        for (id, neighbours) in &self.edges {
+            if neighbours.is_empty() || visited.contains(id) { continue; }
+
+            let mut component = Vec::new();
+            let mut stack = vec![*id];
+            visited.insert(*id);
+            while let Some(current) = stack.pop() {
+                component.push(current);
+                for next in self.edges[&current].keys() {
+                    if visited.insert(*next) {
+                        stack.push(*next);
+                    }
+                }
+            }
+            component.sort();
+            components.push(component);
         }
+
+        components
     }
 
-    fn is_overlapped(&self) -> bool {
-        match &self {
-            State::Overlapped(_) => true,
-            _ => false
+    fn connect(&mut self, id1: u32, id2: u32, area: usize) {
+        self.edges.entry(id1).or_default().insert(id2, area);
+        self.edges.entry(id2).or_default().insert(id1, area);
+    }
+
+    fn remove(&mut self, id: u32) {
+        if let Some(neighbours) = self.edges.remove(&id) {
+            for neighbour in neighbours.keys() {
+                self.edges.get_mut(neighbour).unwrap().remove(&id);
+            }
         }
     }
 }
 
+// claims are kept as rectangles, so memory and time depend
+// on the amount of claims rather than on the fabric size,
+// overlapped area and overlaps are updated on every change
+#[derive(Debug, Default)]
 pub struct Fabric {
-    pieces: Vec<Vec<State>>,
-    active_ids: HashSet<u16>
+    claims: BTreeMap<u32, Claim>,
+    graph: OverlapGraph,
+    overlapped: usize
 }
 impl Fabric {
     pub fn new() -> Self {
-        let active_ids = HashSet::new();
-        let pieces: Vec<Vec<_>> = 
-            (0..1000)
-            .map(|_| (0..1000).map(|_| State::Available).collect())
-            .collect();
-        Fabric { pieces, active_ids }
+        Self::default()
     }
 
+    // claim with already known id replaces the previous one
     pub fn make_claim(&mut self, claim: Claim) {
-        self.active_ids.insert(claim.id);
+        self.withdraw_claim(claim.id);
 
-        for x in 0..claim.size.x {
-            for y in 0..claim.size.y {
-                self.pieces[x+claim.offset.x][y+claim.offset.y].i_need_it(claim.id);
-            }
+        let bounds = claim.bounds();
+        let neighbours = self.overlaps_with(&bounds);
+        self.overlapped += Self::newly_overlapped(&bounds, &neighbours);
+
+        self.graph.edges.insert(claim.id, BTreeMap::new());
+        for (id, area) in neighbours.iter().map(|(id, b)| (*id, bounds.intersection(b).area())) {
+            self.graph.connect(claim.id, id, area);
         }
+        self.claims.insert(claim.id, claim);
+    }
+
+    // returns withdrawn claim, if there was one with such id
+    pub fn withdraw_claim(&mut self, id: u32) -> Option<Claim> {
+        let claim = self.claims.remove(&id)?;
+
+        let bounds = claim.bounds();
+        let neighbours = self.overlaps_with(&bounds);
+        self.overlapped -= Self::newly_overlapped(&bounds, &neighbours);
+        self.graph.remove(id);
+
+        Some(claim)
+    }
+
+    // moves or resizes existing claim, returns its previous placement
+    pub fn amend_claim(&mut self, id: u32, offset: Rectangle, size: Rectangle) -> Option<Claim> {
+        let previous = self.withdraw_claim(id)?;
+        self.make_claim(Claim { id, offset, size });
+        Some(previous)
     }
 
     pub fn count_overlapped(&self) -> usize {
-        self.pieces
-        .iter()
-        .flatten()
-        .filter(|&state| state.is_overlapped())
-        .count()
+        self.overlapped
     }
 
-    pub fn find_magic_id(&self) -> u16 {
-        let mut overlapped_ids = HashSet::new();
+    // the smallest id of a claim which overlaps no other claim
+    pub fn find_magic_id(&self) -> Option<u32> {
+        self.graph.intact_claims().first().cloned()
+    }
 
-        for cell in self.pieces.iter().flatten() {
-            match cell {
-                State::Overlapped(ids) => {
-                    for id in ids {
-                        overlapped_ids.insert(*id);
-                    }
-                },
-                _ => ()
+    pub fn overlap_graph(&self) -> &OverlapGraph {
+        &self.graph
+    }
+
+    // square covered by the most claims, ties resolve to the smallest x, then y
+    pub fn most_contended_square(&self) -> Option<Contention> {
+        let bounds: Vec<_> =
+            self.claims.values().map(Claim::bounds).filter(|b| !b.is_empty()).collect();
+        if bounds.is_empty() { return None; }
+
+        let mut ys: Vec<usize> = bounds.iter().flat_map(|b| vec![b.top, b.bottom]).collect();
+        ys.sort();
+        ys.dedup();
+
+        // removals go first, as right edges aren't part of the claim
+        let mut events: Vec<(usize, i32, usize, usize)> =
+            bounds
+            .iter()
+            .flat_map(|b| vec![(b.left, 1, b.top, b.bottom), (b.right, -1, b.top, b.bottom)])
+            .collect();
+        events.sort();
+
+        let mut tree = DepthTree::new(ys.len() - 1);
+        let mut best: Option<(usize, usize, usize)> = None;
+
+        for group in events.chunk_by(|e1, e2| e1.0 == e2.0) {
+            for (_x, delta, top, bottom) in group {
+                let from = ys.binary_search(top).unwrap();
+                let to = ys.binary_search(bottom).unwrap();
+                tree.update(1, 0, ys.len() - 1, from, to, *delta);
+            }
+            let (depth, segment) = tree.max();
+            if depth > 0 && best.is_none_or(|(best_depth, _, _)| depth as usize > best_depth) {
+                best = Some((depth as usize, group[0].0, ys[segment]));
             }
         }
 
-        *self.active_ids.difference(&overlapped_ids).next().unwrap()
+        best.map(|(_depth, x, y)| match self.state_at(x, y) {
+            State::Reserved(id) => Contention { x, y, claims: vec![id] },
+            State::Overlapped(ids) => Contention { x, y, claims: ids.into_iter().collect() },
+            State::Available => unreachable!()
+        })
+    }
+
+    pub fn state_at(&self, x: usize, y: usize) -> State {
+        let ids: BTreeSet<u32> =
+            self.claims
+            .values()
+            .filter(|claim| {
+                let b = claim.bounds();
+                b.left <= x && x < b.right && b.top <= y && y < b.bottom
+            })
+            .map(|claim| claim.id)
+            .collect();
+
+        match ids.len() {
+            0 => State::Available,
+            1 => State::Reserved(*ids.iter().next().unwrap()),
+            _ => State::Overlapped(ids)
+        }
+    }
+
+    // claims other than the given rectangle itself which intersect it
+    fn overlaps_with(&self, bounds: &Bounds) -> Vec<(u32, Bounds)> {
+        self.claims
+        .values()
+        .map(|claim| (claim.id, claim.bounds()))
+        .filter(|(_id, b)| !bounds.intersection(b).is_empty())
+        .collect()
+    }
+
+    // area which becomes overlapped when rectangle is added to its neighbours:
+    // the part of it covered by exactly one neighbour
+    fn newly_overlapped(bounds: &Bounds, neighbours: &[(u32, Bounds)]) -> usize {
+        let clipped: Vec<_> = neighbours.iter().map(|(_id, b)| bounds.intersection(b)).collect();
+        let (once, twice) = sweep_areas(clipped.iter());
+        once - twice
+    }
+
+    // full recount, incrementally kept value must always be equal to it
+    fn sweep_overlapped(&self) -> usize {
+        let bounds: Vec<_> = self.claims.values().map(Claim::bounds).collect();
+        sweep_areas(bounds.iter()).1
     }
 }
 
-#[derive(Debug, PartialEq)]
-struct Rectangle {
-    x: usize,
-    y: usize
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Rectangle {
+    pub x: usize,
+    pub y: usize
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub struct Claim {
-    id: u16,
+    id: u32,
     offset: Rectangle,
     size: Rectangle
 }
@@ -115,6 +425,50 @@ impl Claim {
             }
         }
     }
+
+    fn bounds(&self) -> Bounds {
+        Bounds {
+            left: self.offset.x,
+            top: self.offset.y,
+            right: self.offset.x + self.size.x,
+            bottom: self.offset.y + self.size.y
+        }
+    }
+}
+
+pub struct Solver;
+impl Solver {
+    fn fabric(input: &str) -> Fabric {
+        let mut fabric = Fabric::new();
+        for claim in input.lines().map(Claim::parse_str) {
+            fabric.make_claim(claim);
+        }
+        fabric
+    }
+}
+impl Solution for Solver {
+    fn part_1(&self, input: &str) -> Option<String> {
+        Some(Self::fabric(input).count_overlapped().to_string())
+    }
+
+    fn part_2(&self, input: &str) -> Option<String> {
+        Self::fabric(input).find_magic_id().map(|id| id.to_string())
+    }
+
+    fn explain(&self, input: &str, trace: &mut Trace) {
+        let fabric = Self::fabric(input);
+        trace.record("claims", fabric.claims.len());
+        trace.record("overlapped square inches", fabric.count_overlapped());
+        let graph = fabric.overlap_graph();
+        trace.record("intact claims", format!("{:?}", graph.intact_claims()));
+        trace.record("conflict components", graph.components().len());
+        if let Some(contention) = fabric.most_contended_square() {
+            trace.record(
+                "most contended square",
+                format!("{},{} claimed {} times", contention.x, contention.y, contention.claims.len())
+            );
+        }
+    }
 }
 
 #[test]
@@ -126,7 +480,7 @@ fn test_example() {
     fabric.make_claim(Claim::parse_str("#3 @ 5,5: 2x2"));
 
     assert_eq!(fabric.count_overlapped(), 4);
-    assert_eq!(fabric.find_magic_id(), 3);
+    assert_eq!(fabric.find_magic_id(), Some(3));
 }
 
 #[test]
@@ -140,3 +494,128 @@ fn test_parsing() {
 
     assert_eq!(claim, expected)
 }
+
+#[test]
+fn test_large_fabric() {
+    let mut fabric = Fabric::new();
+
+    fabric.make_claim(Claim::parse_str("#100000 @ 999999,5000000: 3000x2"));
+    fabric.make_claim(Claim::parse_str("#4000000000 @ 1001998,5000001: 10x10"));
+    fabric.make_claim(Claim::parse_str("#7 @ 0,0: 1x1"));
+
+    assert_eq!(fabric.count_overlapped(), 10);
+    assert_eq!(fabric.find_magic_id(), Some(7));
+    assert_eq!(fabric.state_at(1001998, 5000001), State::Overlapped(vec![100000, 4000000000].into_iter().collect()));
+    assert_eq!(fabric.state_at(1001998, 5000000), State::Reserved(100000));
+    assert_eq!(fabric.state_at(5, 5), State::Available);
+}
+
+#[test]
+fn test_overlap_of_many() {
+    let mut fabric = Fabric::new();
+
+    fabric.make_claim(Claim::parse_str("#1 @ 0,0: 4x4"));
+    fabric.make_claim(Claim::parse_str("#2 @ 1,1: 2x2"));
+    fabric.make_claim(Claim::parse_str("#3 @ 2,2: 3x3"));
+    fabric.make_claim(Claim::parse_str("#4 @ 4,0: 1x1"));
+
+    assert_eq!(fabric.count_overlapped(), 7);
+    assert_eq!(fabric.find_magic_id(), Some(4));
+}
+
+
+#[test]
+fn test_overlap_graph() {
+    let mut fabric = Fabric::new();
+
+    fabric.make_claim(Claim::parse_str("#1 @ 0,0: 4x4"));
+    fabric.make_claim(Claim::parse_str("#2 @ 1,1: 2x2"));
+    fabric.make_claim(Claim::parse_str("#3 @ 2,2: 3x3"));
+    fabric.make_claim(Claim::parse_str("#4 @ 10,10: 1x1"));
+    fabric.make_claim(Claim::parse_str("#5 @ 20,20: 2x2"));
+    fabric.make_claim(Claim::parse_str("#6 @ 21,21: 2x2"));
+    fabric.make_claim(Claim::parse_str("#7 @ 30,30: 1x1"));
+
+    let graph = fabric.overlap_graph();
+
+    assert_eq!(graph.overlaps_of(1), vec![(2, 4), (3, 4)]);
+    assert_eq!(graph.overlaps_of(3), vec![(1, 4), (2, 1)]);
+    assert_eq!(graph.overlaps_of(4), vec![]);
+    assert_eq!(graph.overlaps_of(100), vec![]);
+    assert_eq!(graph.intact_claims(), vec![4, 7]);
+    assert_eq!(graph.components(), vec![vec![1, 2, 3], vec![5, 6]]);
+
+    let expected = Contention { x: 2, y: 2, claims: vec![1, 2, 3] };
+    assert_eq!(fabric.most_contended_square(), Some(expected));
+}
+
+#[test]
+fn test_no_intact_claims() {
+    let mut fabric = Fabric::new();
+
+    fabric.make_claim(Claim::parse_str("#1 @ 0,0: 2x2"));
+    fabric.make_claim(Claim::parse_str("#2 @ 1,1: 2x2"));
+
+    assert_eq!(fabric.find_magic_id(), None);
+    assert_eq!(Fabric::new().most_contended_square(), None);
+}
+
+#[test]
+fn test_withdraw_and_amend() {
+    let mut fabric = Fabric::new();
+
+    fabric.make_claim(Claim::parse_str("#1 @ 1,3: 4x4"));
+    fabric.make_claim(Claim::parse_str("#2 @ 3,1: 4x4"));
+    fabric.make_claim(Claim::parse_str("#3 @ 3,3: 1x1"));
+
+    assert_eq!(fabric.count_overlapped(), 4);
+    assert_eq!(fabric.state_at(3, 3), State::Overlapped(vec![1, 2, 3].into_iter().collect()));
+    assert_eq!(fabric.find_magic_id(), None);
+
+    assert_eq!(fabric.withdraw_claim(2), Some(Claim::parse_str("#2 @ 3,1: 4x4")));
+    assert_eq!(fabric.withdraw_claim(2), None);
+    assert_eq!(fabric.count_overlapped(), 1);
+    assert_eq!(fabric.state_at(3, 3), State::Overlapped(vec![1, 3].into_iter().collect()));
+    assert_eq!(fabric.state_at(4, 3), State::Reserved(1));
+
+    fabric.withdraw_claim(3);
+    assert_eq!(fabric.count_overlapped(), 0);
+    assert_eq!(fabric.state_at(3, 3), State::Reserved(1));
+    assert_eq!(fabric.find_magic_id(), Some(1));
+
+    fabric.withdraw_claim(1);
+    assert_eq!(fabric.state_at(3, 3), State::Available);
+    assert_eq!(fabric.overlap_graph(), &OverlapGraph::default());
+
+    fabric.make_claim(Claim::parse_str("#5 @ 0,0: 2x2"));
+    fabric.make_claim(Claim::parse_str("#6 @ 5,5: 2x2"));
+    assert_eq!(fabric.find_magic_id(), Some(5));
+
+    let previous = fabric.amend_claim(6, Rectangle { x: 1, y: 1 }, Rectangle { x: 3, y: 1 });
+    assert_eq!(previous, Some(Claim::parse_str("#6 @ 5,5: 2x2")));
+    assert_eq!(fabric.count_overlapped(), 1);
+    assert_eq!(fabric.overlap_graph().overlaps_of(5), vec![(6, 1)]);
+    assert_eq!(fabric.amend_claim(7, Rectangle { x: 1, y: 1 }, Rectangle { x: 1, y: 1 }), None);
+}
+
+#[test]
+fn test_incremental_count_matches_sweep() {
+    let mut fabric = Fabric::new();
+    let claims: Vec<_> =
+        (0..40u32)
+        .map(|i| Claim {
+            id: i,
+            offset: Rectangle { x: (i as usize * 7) % 23, y: (i as usize * 11) % 19 },
+            size: Rectangle { x: 1 + i as usize % 6, y: 1 + (i as usize * 3) % 5 }
+        })
+        .collect();
+
+    for claim in &claims {
+        fabric.make_claim(*claim);
+        assert_eq!(fabric.count_overlapped(), fabric.sweep_overlapped());
+    }
+    for claim in claims.iter().step_by(3) {
+        fabric.withdraw_claim(claim.id);
+        assert_eq!(fabric.count_overlapped(), fabric.sweep_overlapped());
+    }
+}
\ No newline at end of file
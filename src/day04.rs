@@ -1,135 +1,612 @@
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
 use itertools::Itertools;
+use crate::solution::{Solution, Trace};
 
-pub fn parse_input(raw: &str) -> Vec<Observation> {
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    // line number is one-based
+    pub line: usize,
+    pub content: String
+}
+
+pub fn parse_input(raw: &str) -> Result<Vec<Observation>, ParseError> {
     raw
     .lines()
-    .map(|r| {
-        let record = match r.chars().nth(19).unwrap() {
-            'f' => RecordType::FallsAsleep,
-            'w' => RecordType::WakesUp,
-            'G' => {
-                let guard_id = r[26..].split(' ').nth(0).unwrap().parse().unwrap();
-                RecordType::BeginsShift(guard_id)
-            },
-            _ => panic!("impossibru")
-        };
-        Observation {
-            year: r[1..5].parse().unwrap(),
-            month: r[6..8].parse().unwrap(),
-            day: r[9..11].parse().unwrap(),
-            hour: r[12..14].parse().unwrap(),
-            minute: r[15..17].parse().unwrap(),
-            record
-        }
+    .enumerate()
+    .filter(|(_i, r)| !r.trim().is_empty())
+    .map(|(i, r)| {
+        Observation::parse_str(r).ok_or_else(|| ParseError { line: i + 1, content: r.to_string() })
     })
     .collect()
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32
+}
+impl Date {
+    pub fn new(year: i32, month: u32, day: u32) -> Option<Self> {
+        let date = Date { year, month, day };
+        if (1..=12).contains(&month) && day >= 1 && day <= date.days_in_month() {
+            Some(date)
+        } else {
+            None
+        }
+    }
+
+    pub fn next_day(&self) -> Date {
+        if self.day < self.days_in_month() {
+            Date { day: self.day + 1, ..*self }
+        } else if self.month < 12 {
+            Date { month: self.month + 1, day: 1, ..*self }
+        } else {
+            Date { year: self.year + 1, month: 1, day: 1 }
+        }
+    }
+
+    fn days_in_month(&self) -> u32 {
+        match self.month {
+            2 if self.is_leap_year() => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31
+        }
+    }
+
+    fn is_leap_year(&self) -> bool {
+        (self.year % 4 == 0 && self.year % 100 != 0) || self.year % 400 == 0
+    }
+}
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct Timestamp {
+    pub date: Date,
+    pub hour: u32,
+    pub minute: u32
+}
+impl Timestamp {
+    // accepts "1518-11-01 23:58" with any spacing around the parts
+    pub fn parse_str(raw: &str) -> Option<Self> {
+        let mut parts = raw.split_whitespace();
+        let mut date = parts.next()?.split('-').map(|n| n.trim().parse().ok());
+        let mut time = parts.next()?.split(':').map(|n| n.trim().parse::<u32>().ok());
+        if parts.next().is_some() { return None; }
+
+        let year = date.next()??;
+        let month = date.next()??;
+        let day = date.next()??;
+        let hour = time.next()??;
+        let minute = time.next()??;
+        if date.next().is_some() || time.next().is_some() || hour > 23 || minute > 59 {
+            return None;
+        }
+
+        Some(Timestamp { date: Date::new(year as i32, month, day)?, hour, minute })
+    }
+
+    // shifts beginning before midnight belong to the night of the next day
+    pub fn shift_date(&self) -> Date {
+        if self.hour >= 12 { self.date.next_day() } else { self.date }
+    }
+
+    // minute of the midnight hour of the given night,
+    // moments before it are clamped to 0 and after it to 60
+    fn midnight_minute(&self, night: Date) -> usize {
+        if self.date < night { 0 }
+        else if self.date > night || self.hour > 0 { MINUTES }
+        else { self.minute as usize }
+    }
+}
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {:02}:{:02}", self.date, self.hour, self.minute)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum RecordType {
     WakesUp,
     FallsAsleep,
     BeginsShift(usize)
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct Observation {
-    year: usize,
-    month: usize,
-    day: usize,
-    hour: usize,
-    minute: usize,
+    timestamp: Timestamp,
     record: RecordType
 }
+impl Observation {
+    pub fn parse_str(raw: &str) -> Option<Self> {
+        let raw = raw.trim().strip_prefix('[')?;
+        let (timestamp, text) = raw.split_at(raw.find(']')?);
+        let timestamp = Timestamp::parse_str(timestamp)?;
+
+        let words: Vec<_> = text[1..].split_whitespace().collect();
+        let record = match words.as_slice() {
+            ["falls", "asleep"] => RecordType::FallsAsleep,
+            ["wakes", "up"] => RecordType::WakesUp,
+            ["Guard", id, "begins", "shift"] => {
+                RecordType::BeginsShift(id.strip_prefix('#')?.parse().ok()?)
+            },
+            _ => return None
+        };
+
+        Some(Observation { timestamp, record })
+    }
+}
+
+const MINUTES: usize = 60;
+
+// one night of a guard, bit n of asleep is set if the guard
+// was asleep during minute n of the midnight hour
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Shift {
+    pub date: Date,
+    pub guard: usize,
+    asleep: u64
+}
+impl Shift {
+    pub fn new(date: Date, guard: usize) -> Self {
+        Shift { date, guard, asleep: 0 }
+    }
+
+    pub fn is_asleep_at(&self, minute: usize) -> bool {
+        minute < MINUTES && self.asleep & (1 << minute) != 0
+    }
+
+    pub fn minutes_asleep(&self) -> usize {
+        self.asleep.count_ones() as usize
+    }
+
+    // (starting minute, length) of every continuous nap
+    pub fn naps(&self) -> Vec<(usize, usize)> {
+        let mut naps = Vec::new();
+        let mut minute = 0;
+        while minute < MINUTES {
+            if self.is_asleep_at(minute) {
+                let start = minute;
+                while self.is_asleep_at(minute) { minute += 1; }
+                naps.push((start, minute - start));
+            } else {
+                minute += 1;
+            }
+        }
+        naps
+    }
+
+    fn sleep(&mut self, from: usize, to: usize) {
+        for minute in from..to.min(MINUTES) {
+            self.asleep |= 1 << minute;
+        }
+    }
+}
+
+// groups records into shifts in chronological order, records which
+// don't follow any shift beginning are skipped
+pub fn group_into_shifts(mut observations: Vec<Observation>) -> Vec<Shift> {
+    observations.sort_by_key(|obs| obs.timestamp);
+
+    let mut shifts: Vec<Shift> = Vec::new();
+    let mut asleep_at = None;
+
+    for obs in observations.iter() {
+        match obs.record {
+            RecordType::BeginsShift(guard) => {
+                shifts.push(Shift::new(obs.timestamp.shift_date(), guard));
+                asleep_at = None;
+            },
+            RecordType::FallsAsleep => asleep_at = Some(obs.timestamp),
+            RecordType::WakesUp => {
+                if let (Some(shift), Some(from)) = (shifts.last_mut(), asleep_at.take()) {
+                    let from = from.midnight_minute(shift.date);
+                    let to = obs.timestamp.midnight_minute(shift.date);
+                    shift.sleep(from, to);
+                }
+            }
+        }
+    }
+
+    shifts
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Diagnostic {
+    RecordBeforeFirstShift(Timestamp),
+    WakeWithoutSleep(Timestamp),
+    SleepWhileAsleep(Timestamp),
+    // shift begins while the previous guard is still asleep
+    AsleepAtShiftChange(Timestamp),
+    // the last guard never wakes up, timestamp is of falling asleep
+    AsleepAtEnd(Timestamp)
+}
+impl Diagnostic {
+    pub fn timestamp(&self) -> Timestamp {
+        match self {
+            Diagnostic::RecordBeforeFirstShift(ts) |
+            Diagnostic::WakeWithoutSleep(ts) |
+            Diagnostic::SleepWhileAsleep(ts) |
+            Diagnostic::AsleepAtShiftChange(ts) |
+            Diagnostic::AsleepAtEnd(ts) => *ts
+        }
+    }
+}
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            Diagnostic::RecordBeforeFirstShift(_) => "record before any shift begins",
+            Diagnostic::WakeWithoutSleep(_) => "guard wakes up without falling asleep",
+            Diagnostic::SleepWhileAsleep(_) => "guard falls asleep while already asleep",
+            Diagnostic::AsleepAtShiftChange(_) => "shift begins while previous guard is asleep",
+            Diagnostic::AsleepAtEnd(_) => "guard never wakes up"
+        };
+        write!(f, "[{}] {}", self.timestamp(), message)
+    }
+}
+
+// checks that records of every shift alternate between
+// falling asleep and waking up, in chronological order
+pub fn validate(observations: &[Observation]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut in_shift = false;
+    let mut asleep_at: Option<Timestamp> = None;
+
+    for obs in observations.iter().sorted_by_key(|obs| obs.timestamp) {
+        let ts = obs.timestamp;
+        match obs.record {
+            RecordType::BeginsShift(_) => {
+                if asleep_at.take().is_some() {
+                    diagnostics.push(Diagnostic::AsleepAtShiftChange(ts));
+                }
+                in_shift = true;
+            },
+            _ if !in_shift => diagnostics.push(Diagnostic::RecordBeforeFirstShift(ts)),
+            RecordType::FallsAsleep => {
+                if asleep_at.is_some() {
+                    diagnostics.push(Diagnostic::SleepWhileAsleep(ts));
+                } else {
+                    asleep_at = Some(ts);
+                }
+            },
+            RecordType::WakesUp => {
+                if asleep_at.take().is_none() {
+                    diagnostics.push(Diagnostic::WakeWithoutSleep(ts));
+                }
+            }
+        }
+    }
+
+    if let Some(ts) = asleep_at {
+        diagnostics.push(Diagnostic::AsleepAtEnd(ts));
+    }
+
+    diagnostics
+}
+
+// sleep statistics of one guard over all their shifts
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct GuardStats {
+    pub guard: usize,
+    // times asleep at every minute of the midnight hour
+    pub minutes: [usize; MINUTES],
+    pub nights: usize,
+    pub nights_asleep: usize,
+    // (starting minute, length) of the longest nap, the earliest one on ties
+    pub longest_nap: (usize, usize)
+}
+impl GuardStats {
+    fn new(guard: usize) -> Self {
+        GuardStats { guard, minutes: [0; MINUTES], nights: 0, nights_asleep: 0, longest_nap: (0, 0) }
+    }
+
+    pub fn minutes_asleep(&self) -> usize {
+        self.minutes.iter().sum()
+    }
+
+    // (minute, times asleep at it), the earliest minute on ties
+    pub fn sleepiest_minute(&self) -> (usize, usize) {
+        let mut best = (0, self.minutes[0]);
+        for (minute, times) in self.minutes.iter().enumerate() {
+            if *times > best.1 { best = (minute, *times); }
+        }
+        best
+    }
+}
+
+// fraction which is compared exactly, plain counts are over one
+#[derive(Debug, Clone, Copy)]
+pub struct Score {
+    pub numerator: usize,
+    pub denominator: usize
+}
+impl Score {
+    pub fn count(count: usize) -> Self {
+        Score { numerator: count, denominator: 1 }
+    }
+
+    pub fn share(part: usize, whole: usize) -> Self {
+        assert!(whole > 0, "share of nothing");
+        Score { numerator: part, denominator: whole }
+    }
+
+    // cross-multiplied, so different fractions never round into a tie
+    fn cross(&self, other: &Score) -> (u128, u128) {
+        (self.numerator as u128 * other.denominator as u128, other.numerator as u128 * self.denominator as u128)
+    }
+}
+impl PartialEq for Score {
+    fn eq(&self, other: &Score) -> bool {
+        let (left, right) = self.cross(other);
+        left == right
+    }
+}
+impl Eq for Score {}
+impl PartialOrd for Score {
+    fn partial_cmp(&self, other: &Score) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Score {
+    fn cmp(&self, other: &Score) -> Ordering {
+        let (left, right) = self.cross(other);
+        left.cmp(&right)
+    }
+}
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.denominator {
+            1 => write!(f, "{}", self.numerator),
+            _ => write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Choice {
+    pub guard: usize,
+    pub minute: usize,
+    pub score: Score
+}
+impl Choice {
+    pub fn answer(&self) -> usize {
+        self.guard * self.minute
+    }
+}
+
+// strategy scores every guard who ever slept and picks a minute for them,
+// the guard with the highest score wins and ties go to the smallest guard id
+pub trait GuardStrategy {
+    fn name(&self) -> &'static str;
+
+    // returns (score, minute) for the guard
+    fn evaluate(&self, stats: &GuardStats) -> (Score, usize);
+}
+
+// strategy 1: most minutes asleep in total, then their sleepiest minute
+pub struct MostMinutesAsleep;
+impl GuardStrategy for MostMinutesAsleep {
+    fn name(&self) -> &'static str { "most minutes asleep" }
+
+    fn evaluate(&self, stats: &GuardStats) -> (Score, usize) {
+        (Score::count(stats.minutes_asleep()), stats.sleepiest_minute().0)
+    }
+}
+
+// strategy 2: most frequently asleep at the same minute
+pub struct MostFrequentMinute;
+impl GuardStrategy for MostFrequentMinute {
+    fn name(&self) -> &'static str { "most frequent minute" }
+
+    fn evaluate(&self, stats: &GuardStats) -> (Score, usize) {
+        let (minute, times) = stats.sleepiest_minute();
+        (Score::count(times), minute)
+    }
+}
+
+// most nights with any sleep at all, then their sleepiest minute
+pub struct MostNightsAsleep;
+impl GuardStrategy for MostNightsAsleep {
+    fn name(&self) -> &'static str { "most nights asleep" }
+
+    fn evaluate(&self, stats: &GuardStats) -> (Score, usize) {
+        (Score::count(stats.nights_asleep), stats.sleepiest_minute().0)
+    }
+}
+
+// the longest single nap, minute is the one it started at
+pub struct LongestNap;
+impl GuardStrategy for LongestNap {
+    fn name(&self) -> &'static str { "longest nap" }
+
+    fn evaluate(&self, stats: &GuardStats) -> (Score, usize) {
+        let (start, length) = stats.longest_nap;
+        (Score::count(length), start)
+    }
+}
+
+// minute with the highest share of the guard's nights spent asleep at it,
+// score is that share as an exact fraction. a guard seen on a single night
+// trivially scores 100% with any minute slept, as does anyone asleep at the
+// same minute every night, few nights weigh as much as many
+pub struct MostConsistentMinute;
+impl GuardStrategy for MostConsistentMinute {
+    fn name(&self) -> &'static str { "most consistent minute" }
+
+    fn evaluate(&self, stats: &GuardStats) -> (Score, usize) {
+        let (minute, times) = stats.sleepiest_minute();
+        (Score::share(times, stats.nights.max(1)), minute)
+    }
+}
+
+pub fn built_in_strategies() -> Vec<Box<dyn GuardStrategy>> {
+    vec![
+        Box::new(MostMinutesAsleep),
+        Box::new(MostFrequentMinute),
+        Box::new(MostNightsAsleep),
+        Box::new(LongestNap),
+        Box::new(MostConsistentMinute),
+    ]
+}
 
 #[derive(Debug)]
 pub struct ObservationMap {
-    results: HashMap<usize, HashMap<usize, usize>>
+    shifts: Vec<Shift>,
+    // only guards who ever slept, ordered by id
+    stats: BTreeMap<usize, GuardStats>
 }
 impl ObservationMap {
-    pub fn build_from_observations(mut observations: Vec<Observation>) -> Self {
-        observations.sort_by(|o1, o2| {
-            o1.year.cmp(&o2.year)
-            .then(o1.month.cmp(&o2.month))
-            .then(o1.day.cmp(&o2.day))
-            .then(o1.hour.cmp(&o2.hour))
-            .then(o1.minute.cmp(&o2.minute))
-        });
-
-        let mut guard_id = 0;
-        let mut asleep_at = 0;
-
-        let mut results = HashMap::new();
-
-        for obs in observations.iter() {
-            match obs.record {
-                RecordType::BeginsShift(id) => guard_id = id,
-                RecordType::FallsAsleep => asleep_at = obs.minute,
-                RecordType::WakesUp => {
-                    match results.get_mut(&guard_id) {
-                        Some(map) => {
-                            Self::put_inner(map, asleep_at, obs.minute);
-                        },
-                        None => {
-                            let mut map = HashMap::new();
-                            Self::put_inner(&mut map, asleep_at, obs.minute);
-                            results.insert(guard_id, map);
-                        }
-                    }
+    pub fn build_from_observations(observations: Vec<Observation>) -> Self {
+        let shifts = group_into_shifts(observations);
+        let mut stats: BTreeMap<usize, GuardStats> = BTreeMap::new();
+
+        for shift in &shifts {
+            let guard_stats = stats.entry(shift.guard).or_insert_with(|| GuardStats::new(shift.guard));
+            guard_stats.nights += 1;
+            if shift.minutes_asleep() > 0 { guard_stats.nights_asleep += 1; }
+            for minute in (0..MINUTES).filter(|m| shift.is_asleep_at(*m)) {
+                guard_stats.minutes[minute] += 1;
+            }
+            for (start, length) in shift.naps() {
+                let (best_start, best_length) = guard_stats.longest_nap;
+                if length > best_length || (length == best_length && start < best_start) {
+                    guard_stats.longest_nap = (start, length);
                 }
             }
         }
+        stats.retain(|_guard, guard_stats| guard_stats.nights_asleep > 0);
 
-        Self { results }
+        Self { shifts, stats }
     }
 
-    pub fn strategy_1(&self) -> usize {
-        let selected_guard =
-            self.results
-            .iter()
-            .map(|(guard, stats)| {
-                (guard, stats.iter().fold(0, |acc, (_key, val)| acc + val))
-            })
-            .sorted_by_key(|x| std::usize::MAX - x.1)
-            .map(|(guard, _stats)| *guard)
-            .nth(0)
-            .unwrap();
+    // refuses to build the map, and so to compute any strategy, for invalid logs
+    pub fn build_strict(observations: Vec<Observation>) -> Result<Self, Vec<Diagnostic>> {
+        let diagnostics = validate(&observations);
+        if diagnostics.is_empty() {
+            Ok(Self::build_from_observations(observations))
+        } else {
+            Err(diagnostics)
+        }
+    }
+
+    pub fn shifts(&self) -> &[Shift] {
+        &self.shifts
+    }
 
-        let selected_min =
-            self.results.get(&selected_guard).unwrap()
-            .iter()
-            .sorted_by_key(|(_minute, occurrences)| std::usize::MAX - *occurrences)
-            .map(|(minute, _occurrences)| *minute)
-            .nth(0)
-            .unwrap();
+    pub fn guard_stats(&self, guard: usize) -> Option<&GuardStats> {
+        self.stats.get(&guard)
+    }
 
-        selected_guard * selected_min
+    // None when nobody ever slept
+    pub fn apply(&self, strategy: &dyn GuardStrategy) -> Option<Choice> {
+        let mut best: Option<Choice> = None;
+        // guards are visited by ascending id, so only a strictly better score wins
+        for stats in self.stats.values() {
+            let (score, minute) = strategy.evaluate(stats);
+            if best.is_none_or(|choice| score > choice.score) {
+                best = Some(Choice { guard: stats.guard, minute, score });
+            }
+        }
+        best
     }
 
-    pub fn strategy_2(&self) -> usize {
-        let mut selected_guard = 0;
-        let mut selected_min = 0;
-        let mut max_occurences = 0;
+    // chart from the puzzle text, one row per shift in chronological order
+    pub fn render_chart(&self) -> String {
+        let id_width =
+            self.shifts.iter()
+            .map(|shift| format!("#{}", shift.guard).len())
+            .max()
+            .unwrap_or(0)
+            .max("ID".len());
 
-        for (guard, statistics) in self.results.iter() {
-            for (minute, occurrences) in statistics.iter() {
-                if *occurrences > max_occurences {
-                    max_occurences = *occurrences;
-                    selected_guard = *guard;
-                    selected_min = *minute;
-                }
+        let mut chart = String::new();
+        let padding = " ".repeat("MM-DD".len() + 2 + id_width + 2);
+        chart.push_str(&format!("{:<7}{:<width$}Minute\n", "Date", "ID", width = id_width + 2));
+        chart.push_str(&padding);
+        chart.extend((0..MINUTES).map(|m| std::char::from_digit((m / 10) as u32, 10).unwrap()));
+        chart.push('\n');
+        chart.push_str(&padding);
+        chart.extend((0..MINUTES).map(|m| std::char::from_digit((m % 10) as u32, 10).unwrap()));
+        chart.push('\n');
+
+        for shift in &self.shifts {
+            chart.push_str(&format!(
+                "{:02}-{:02}  {:<width$}",
+                shift.date.month,
+                shift.date.day,
+                format!("#{}", shift.guard),
+                width = id_width + 2
+            ));
+            chart.extend((0..MINUTES).map(|m| if shift.is_asleep_at(m) { '#' } else { '.' }));
+            chart.push('\n');
+        }
+
+        chart
+    }
+
+    // "guard,0,1,...,59" header and times asleep at every minute,
+    // one row per guard who had a shift, ordered by guard id
+    pub fn histograms_csv(&self) -> String {
+        let mut csv = String::from("guard");
+        for minute in 0..MINUTES {
+            csv.push_str(&format!(",{}", minute));
+        }
+        csv.push('\n');
+
+        let guards: BTreeSet<usize> = self.shifts.iter().map(|shift| shift.guard).collect();
+        for guard in guards {
+            let minutes = self.stats.get(&guard).map_or([0; MINUTES], |stats| stats.minutes);
+            csv.push_str(&guard.to_string());
+            for times in minutes.iter() {
+                csv.push_str(&format!(",{}", times));
             }
+            csv.push('\n');
         }
-        
-        selected_guard * selected_min
+
+        csv
+    }
+
+    // None when no guard ever slept
+    pub fn strategy_1(&self) -> Option<usize> {
+        self.apply(&MostMinutesAsleep).map(|choice| choice.answer())
+    }
+
+    pub fn strategy_2(&self) -> Option<usize> {
+        self.apply(&MostFrequentMinute).map(|choice| choice.answer())
+    }
+}
+
+pub struct Solver;
+impl Solution for Solver {
+    fn part_1(&self, input: &str) -> Option<String> {
+        let obs_map = ObservationMap::build_strict(parse_input(input).unwrap()).unwrap();
+        obs_map.apply(&MostMinutesAsleep).map(|choice| choice.answer().to_string())
+    }
+
+    fn part_2(&self, input: &str) -> Option<String> {
+        let obs_map = ObservationMap::build_strict(parse_input(input).unwrap()).unwrap();
+        obs_map.apply(&MostFrequentMinute).map(|choice| choice.answer().to_string())
     }
 
-    fn put_inner(map: &mut HashMap<usize, usize>, asleep_at: usize, woke_at: usize) {
-        for min in asleep_at..woke_at {
-            match map.get_mut(&min) {
-                Some(amount) => *amount += 1,
-                None => { map.insert(min, 1); }
+    fn explain(&self, input: &str, trace: &mut Trace) {
+        let obs_map = ObservationMap::build_strict(parse_input(input).unwrap()).unwrap();
+
+        for strategy in built_in_strategies() {
+            let section = trace.section(strategy.name());
+            match obs_map.apply(strategy.as_ref()) {
+                Some(choice) => {
+                    section.record("guard", choice.guard);
+                    section.record("minute", choice.minute);
+                    section.record("score", choice.score);
+                },
+                None => section.record("guard", "nobody slept")
             }
         }
     }
@@ -138,39 +615,61 @@ impl ObservationMap {
 #[test]
 fn test_parsing() {
     let test_data = r"[1518-11-01 01:02] Guard #10 begins shift
-[1520-09-03 05:06] wakes up
-[1519-10-02 03:04] falls asleep";
+[1520-09-03  05:06]   wakes up
+  [ 1519-10-02 03:04 ] falls  asleep";
 
-    let parsed = parse_input(test_data);
+    let parsed = parse_input(test_data).unwrap();
 
     let expected = vec![
         Observation {
-            year: 1518,
-            month: 11,
-            day: 1,
-            hour: 1,
-            minute: 2,
+            timestamp: Timestamp { date: Date { year: 1518, month: 11, day: 1 }, hour: 1, minute: 2 },
             record: RecordType::BeginsShift(10)
         },
         Observation {
-            year: 1520,
-            month: 9,
-            day: 3,
-            hour: 5,
-            minute: 6,
+            timestamp: Timestamp { date: Date { year: 1520, month: 9, day: 3 }, hour: 5, minute: 6 },
             record: RecordType::WakesUp
         },
         Observation {
-            year: 1519,
-            month: 10,
-            day: 2,
-            hour: 3,
-            minute: 4,
+            timestamp: Timestamp { date: Date { year: 1519, month: 10, day: 2 }, hour: 3, minute: 4 },
             record: RecordType::FallsAsleep
         },
     ];
 
-    assert_eq!(parsed, expected)
+    assert_eq!(parsed, expected);
+
+    let broken = "[1518-11-01 00:00] Guard #10 begins shift\n[1518-13-01 00:05] falls asleep";
+    assert_eq!(parse_input(broken), Err(ParseError { line: 2, content: String::from("[1518-13-01 00:05] falls asleep") }));
+    assert!(Observation::parse_str("[1518-11-01 00:05] dances").is_none());
+}
+
+#[test]
+fn test_dates() {
+    assert_eq!(Date::new(1518, 2, 28).unwrap().next_day(), Date { year: 1518, month: 3, day: 1 });
+    assert_eq!(Date::new(1600, 2, 28).unwrap().next_day(), Date { year: 1600, month: 2, day: 29 });
+    assert_eq!(Date::new(1518, 12, 31).unwrap().next_day(), Date { year: 1519, month: 1, day: 1 });
+    assert_eq!(Date::new(1518, 2, 29), None);
+    assert_eq!(Date::new(1518, 11, 5).unwrap().to_string(), "1518-11-05");
+}
+
+#[test]
+fn test_shifts() {
+    let test_data = r"[1518-11-02 00:50] wakes up
+[1518-10-31 23:58] Guard #99 begins shift
+[1518-11-01 00:40] falls asleep
+[1518-11-01 00:50] wakes up
+[1518-12-31 23:50] Guard #10 begins shift
+[1518-12-31 23:55] falls asleep
+[1519-01-01 00:03] wakes up";
+
+    let shifts = group_into_shifts(parse_input(test_data).unwrap());
+
+    assert_eq!(shifts.len(), 2);
+    assert_eq!(shifts[0].date, Date { year: 1518, month: 11, day: 1 });
+    assert_eq!(shifts[0].guard, 99);
+    assert_eq!(shifts[0].minutes_asleep(), 10);
+    assert!(shifts[0].is_asleep_at(40) && !shifts[0].is_asleep_at(50));
+    assert_eq!(shifts[1].date, Date { year: 1519, month: 1, day: 1 });
+    assert_eq!(shifts[1].minutes_asleep(), 3);
 }
 
 #[test]
@@ -193,10 +692,133 @@ fn test_strategies() {
 [1518-11-05 00:45] falls asleep
 [1518-11-05 00:55] wakes up";
 
-    let parsed = parse_input(test_data);
+    let parsed = parse_input(test_data).unwrap();
 
     let obs_result = ObservationMap::build_from_observations(parsed);
 
-    assert_eq!(obs_result.strategy_1(), 240);
-    assert_eq!(obs_result.strategy_2(), 4455);
-}
\ No newline at end of file
+    assert_eq!(obs_result.strategy_1(), Some(240));
+    assert_eq!(obs_result.strategy_2(), Some(4455));
+
+    let choose = |strategy: &dyn GuardStrategy| obs_result.apply(strategy).unwrap();
+    assert_eq!(choose(&MostMinutesAsleep), Choice { guard: 10, minute: 24, score: Score::count(50) });
+    assert_eq!(choose(&MostFrequentMinute), Choice { guard: 99, minute: 45, score: Score::count(3) });
+    assert_eq!(choose(&MostNightsAsleep), Choice { guard: 99, minute: 45, score: Score::count(3) });
+    assert_eq!(choose(&LongestNap), Choice { guard: 10, minute: 30, score: Score::count(25) });
+    assert_eq!(choose(&MostConsistentMinute), Choice { guard: 10, minute: 24, score: Score::share(2, 2) });
+}
+
+#[test]
+fn test_strategy_ties() {
+    let test_data = r"[1518-11-01 00:00] Guard #99 begins shift
+[1518-11-01 00:10] falls asleep
+[1518-11-01 00:20] wakes up
+[1518-11-02 00:00] Guard #10 begins shift
+[1518-11-02 00:30] falls asleep
+[1518-11-02 00:40] wakes up
+[1518-11-03 00:00] Guard #10 begins shift
+[1518-11-03 00:05] falls asleep
+[1518-11-03 00:10] wakes up
+[1518-11-03 00:30] falls asleep
+[1518-11-03 00:35] wakes up
+[1518-11-04 00:00] Guard #7 begins shift";
+
+    let obs_result = ObservationMap::build_strict(parse_input(test_data).unwrap()).unwrap();
+
+    // guard 7 never sleeps, 10 and 99 tie on many scores and the smaller id wins
+    assert!(obs_result.guard_stats(7).is_none());
+    assert_eq!(obs_result.apply(&MostMinutesAsleep), Some(Choice { guard: 10, minute: 30, score: Score::count(20) }));
+    assert_eq!(obs_result.apply(&LongestNap), Some(Choice { guard: 10, minute: 30, score: Score::count(10) }));
+    assert_eq!(obs_result.apply(&MostConsistentMinute), Some(Choice { guard: 10, minute: 30, score: Score::share(1, 1) }));
+    assert_eq!(obs_result.apply(&MostFrequentMinute), Some(Choice { guard: 10, minute: 30, score: Score::count(2) }));
+
+    let nobody = ObservationMap::build_from_observations(parse_input("[1518-11-04 00:00] Guard #7 begins shift").unwrap());
+    assert_eq!(nobody.apply(&MostNightsAsleep), None);
+    assert_eq!(nobody.strategy_1(), None);
+    assert_eq!(Solver.part_1("[1518-11-04 00:00] Guard #7 begins shift"), None);
+}
+
+#[test]
+fn test_validation() {
+    let test_data = r"[1518-11-01 00:01] wakes up
+[1518-11-01 00:00] Guard #10 begins shift
+[1518-11-01 00:05] wakes up
+[1518-11-01 00:10] falls asleep
+[1518-11-01 00:15] falls asleep
+[1518-11-01 00:20] wakes up
+[1518-11-01 00:30] falls asleep
+[1518-11-01 23:58] Guard #99 begins shift
+[1518-11-02 00:40] falls asleep";
+
+    let observations = parse_input(test_data).unwrap();
+    let at = |raw: &str| Timestamp::parse_str(raw).unwrap();
+
+    let expected = vec![
+        Diagnostic::WakeWithoutSleep(at("1518-11-01 00:01")),
+        Diagnostic::WakeWithoutSleep(at("1518-11-01 00:05")),
+        Diagnostic::SleepWhileAsleep(at("1518-11-01 00:15")),
+        Diagnostic::AsleepAtShiftChange(at("1518-11-01 23:58")),
+        Diagnostic::AsleepAtEnd(at("1518-11-02 00:40")),
+    ];
+
+    assert_eq!(validate(&observations), expected);
+    assert_eq!(expected[2].to_string(), "[1518-11-01 00:15] guard falls asleep while already asleep");
+    assert!(ObservationMap::build_strict(observations).is_err());
+
+    let before_shift = parse_input("[1518-11-01 00:05] falls asleep").unwrap();
+    let expected = vec![Diagnostic::RecordBeforeFirstShift(at("1518-11-01 00:05"))];
+    assert_eq!(validate(&before_shift), expected);
+}
+
+#[test]
+fn test_chart_and_csv() {
+    let test_data = r"[1518-11-01 00:00] Guard #10 begins shift
+[1518-11-01 00:05] falls asleep
+[1518-11-01 00:25] wakes up
+[1518-11-01 00:30] falls asleep
+[1518-11-01 00:55] wakes up
+[1518-11-01 23:58] Guard #99 begins shift
+[1518-11-02 00:40] falls asleep
+[1518-11-02 00:50] wakes up
+[1518-11-03 00:05] Guard #10 begins shift
+[1518-11-03 00:24] falls asleep
+[1518-11-03 00:29] wakes up
+[1518-11-04 00:02] Guard #99 begins shift
+[1518-11-04 00:36] falls asleep
+[1518-11-04 00:46] wakes up
+[1518-11-05 00:03] Guard #99 begins shift
+[1518-11-05 00:45] falls asleep
+[1518-11-05 00:55] wakes up";
+
+    let obs_result = ObservationMap::build_strict(parse_input(test_data).unwrap()).unwrap();
+
+    let expected_chart = r"Date   ID   Minute
+            000000000011111111112222222222333333333344444444445555555555
+            012345678901234567890123456789012345678901234567890123456789
+11-01  #10  .....####################.....#########################.....
+11-02  #99  ........................................##########..........
+11-03  #10  ........................#####...............................
+11-04  #99  ....................................##########..............
+11-05  #99  .............................................##########.....
+";
+
+    assert_eq!(obs_result.render_chart(), expected_chart);
+
+    let csv = obs_result.histograms_csv();
+    let lines: Vec<_> = csv.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("guard,0,1,2,"));
+    assert!(lines[0].ends_with(",58,59"));
+    assert!(lines[1].starts_with("10,0,0,0,0,0,1,1,"));
+    assert_eq!(lines[1].split(',').nth(24 + 1), Some("2"));
+    assert_eq!(lines[2].split(',').nth(45 + 1), Some("3"));
+}
+
+#[test]
+fn test_score_comparison() {
+    // both round down to 66%, but the shares differ
+    assert!(Score::share(2, 3) > Score::share(200, 301));
+    assert_eq!(Score::share(2, 4), Score::share(1, 2));
+    assert_eq!(Score::share(3, 3), Score::count(1));
+    assert_eq!(Score::share(200, 301).to_string(), "200/301");
+    assert_eq!(Score::count(7).to_string(), "7");
+}
@@ -1,48 +1,339 @@
-use std::iter::FromIterator;
-
-pub fn reduce_and_measure(input: &str) -> usize {
-    let mut polymer = Vec::from_iter(input.chars());
-    let mut i = 0;
-    let mut j = 1;
-    let polymer_len = polymer.len();
-    
-    while j < polymer_len {
-        let char_left = polymer[i];
-        let char_right = polymer[j];
-        
-        if  char_left != char_right &&
-            char_left.to_ascii_lowercase() == char_right.to_ascii_lowercase()
-        {
-            polymer[i] = '_';
-            polymer[j] = '_';
-            j += 1;
-            while polymer[i] == '_' && i > 0 {
-                i -= 1;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::thread;
+use crate::solution::{Solution, Trace};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PolymerError {
+    // offset is in bytes from the start of the input
+    InvalidUnit { offset: usize, unit: char }
+}
+impl fmt::Display for PolymerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PolymerError::InvalidUnit { offset, unit } =>
+                write!(f, "unit {:?} at offset {} is not known to reaction rules", unit, offset)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum StreamError {
+    Io(io::Error),
+    Polymer(PolymerError)
+}
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StreamError::Io(err) => write!(f, "can't stream polymer: {}", err),
+            StreamError::Polymer(err) => err.fmt(f)
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct RulesError {
+    pub line: usize,
+    pub content: String
+}
+impl fmt::Display for RulesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: expected one or two unit tokens, got {:?}", self.line, self.content)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Reaction {
+    pub polymer: String,
+    // amount of surviving units, tokens may be longer than one character
+    pub units: usize,
+    // amount of annihilated unit pairs
+    pub reactions: usize
+}
+impl Reaction {
+    pub fn len(&self) -> usize {
+        self.units
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.units == 0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Rules {
+    // puzzle rule, same ascii letter of the opposite case
+    OppositeCase,
+    // units are token ids, pairs are stored with the smaller id first
+    Table { ids: BTreeMap<String, usize>, tokens: Vec<String>, pairs: BTreeSet<(usize, usize)> }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReactionRules {
+    rules: Rules
+}
+impl Default for ReactionRules {
+    fn default() -> Self {
+        ReactionRules { rules: Rules::OppositeCase }
+    }
+}
+impl ReactionRules {
+    // empty table, every unit has to be added before it can appear in a polymer
+    pub fn table() -> Self {
+        ReactionRules {
+            rules: Rules::Table { ids: BTreeMap::new(), tokens: Vec::new(), pairs: BTreeSet::new() }
+        }
+    }
+
+    // one rule per line: `a A` makes the two units annihilate each other,
+    // a single token declares inert unit, empty lines and `#` comments are skipped
+    pub fn parse_str(text: &str) -> Result<Self, RulesError> {
+        let mut rules = ReactionRules::table();
+        for (i, raw) in text.lines().enumerate() {
+            let content = raw.split('#').next().unwrap().trim();
+            let tokens: Vec<&str> = content.split_whitespace().collect();
+            match tokens[..] {
+                [] => {},
+                [unit] => { rules.add_unit(unit); },
+                [first, second] => rules.add_pair(first, second),
+                _ => return Err(RulesError { line: i + 1, content: raw.to_string() })
             }
-        } else {
-            i = j;
-            j += 1;
+        }
+        Ok(rules)
+    }
+
+    pub fn add_unit(&mut self, token: &str) -> usize {
+        assert!(!token.is_empty(), "unit token can't be empty");
+        if let Rules::OppositeCase = self.rules {
+            *self = ReactionRules::table();
+        }
+        match &mut self.rules {
+            Rules::Table { ids, tokens, .. } => {
+                let next_id = tokens.len();
+                let id = *ids.entry(token.to_string()).or_insert(next_id);
+                if id == next_id {
+                    tokens.push(token.to_string());
+                }
+                id
+            },
+            Rules::OppositeCase => unreachable!()
+        }
+    }
+
+    // pairs are symmetric, adding rule to the default rules replaces them with a table
+    pub fn add_pair(&mut self, first: &str, second: &str) {
+        let first = self.add_unit(first);
+        let second = self.add_unit(second);
+        if let Rules::Table { pairs, .. } = &mut self.rules {
+            pairs.insert((first.min(second), first.max(second)));
+        }
+    }
+
+    // units which survived so far are kept on a stack, every new unit either
+    // annihilates with the top of it or is pushed there, trailing line end is ignored
+    pub fn react(&self, input: &str) -> Result<Reaction, PolymerError> {
+        let input = input.trim_end();
+        match &self.rules {
+            Rules::OppositeCase => {
+                let invalid = input.char_indices().find(|(_, unit)| !unit.is_ascii_alphabetic());
+                if let Some((offset, unit)) = invalid {
+                    return Err(PolymerError::InvalidUnit { offset, unit });
+                }
+
+                let (stack, reactions) = react_units(input.bytes());
+                let units = stack.len();
+                let polymer = String::from_utf8(stack).unwrap();
+                Ok(Reaction { polymer, units, reactions })
+            },
+            Rules::Table { ids, tokens, pairs } => {
+                let mut stack: Vec<usize> = Vec::new();
+                let mut reactions = 0;
+                let longest = tokens.iter().map(|token| token.len()).max().unwrap_or(0);
+
+                let mut offset = 0;
+                while offset < input.len() {
+                    // the longest known token wins
+                    let (id, length) = (1..=longest.min(input.len() - offset))
+                        .rev()
+                        .filter_map(|length| input
+                            .get(offset..offset + length)
+                            .and_then(|token| ids.get(token))
+                            .map(|&id| (id, length))
+                        )
+                        .next()
+                        .ok_or_else(|| {
+                            let unit = input[offset..].chars().next().unwrap();
+                            PolymerError::InvalidUnit { offset, unit }
+                        })?;
+                    offset += length;
+
+                    match stack.last() {
+                        Some(&top) if pairs.contains(&(top.min(id), top.max(id))) => {
+                            stack.pop();
+                            reactions += 1;
+                        },
+                        _ => stack.push(id)
+                    }
+                }
+
+                let polymer = stack.iter().map(|&id| tokens[id].as_str()).collect();
+                Ok(Reaction { polymer, units: stack.len(), reactions })
+            }
+        }
+    }
+}
+
+pub fn react(input: &str) -> Result<Reaction, PolymerError> {
+    ReactionRules::default().react(input)
+}
+
+// expects ascii letters only, returns surviving units and amount of reactions
+fn react_units<I: Iterator<Item = u8>>(units: I) -> (Vec<u8>, usize) {
+    let mut stack: Vec<u8> = Vec::with_capacity(units.size_hint().0);
+    let reactions = react_onto(&mut stack, units);
+    (stack, reactions)
+}
+
+// continues reaction of the already reduced stack with more units
+fn react_onto<I: Iterator<Item = u8>>(stack: &mut Vec<u8>, units: I) -> usize {
+    let mut reactions = 0;
+    for unit in units {
+        match stack.last() {
+            // same letter of the opposite case differs only in this bit
+            Some(top) if top ^ unit == 0x20 => {
+                stack.pop();
+                reactions += 1;
+            },
+            _ => stack.push(unit)
         }
     }
+    reactions
+}
+
+const CHUNK_SIZE: usize = 64 * 1024;
 
-    polymer.into_iter().filter(|ch| *ch != '_').count()
+#[derive(Debug, PartialEq, Eq)]
+pub struct StreamSummary {
+    pub units_read: usize,
+    pub units: usize,
+    pub reactions: usize
 }
 
-pub fn cut_reduce_and_measure(input: &str) -> usize {
-    let mut min_length = std::usize::MAX;
-    for letter in (b'a'..=b'z').map(char::from) {
-        let new_polymer: String =
-            input
-            .chars()
-            .filter(|ch|
-                *ch != letter &&
-                *ch != letter.to_ascii_uppercase()
-            )
+// reduces polymer by the puzzle rule reading it in chunks, so only the surviving
+// units are kept in memory, they are written out once the input is exhausted.
+// like `react`, trailing whitespace is ignored, anywhere else it is an error
+pub fn reduce_stream<R: Read, W: Write>(mut reader: R, mut writer: W) -> Result<StreamSummary, StreamError> {
+    let mut stack: Vec<u8> = Vec::new();
+    let mut chunk = vec![0; CHUNK_SIZE];
+    let mut reactions = 0;
+    let mut units_read = 0;
+    let mut offset = 0;
+    // first whitespace seen, only more whitespace may follow it
+    let mut trailing: Option<(usize, char)> = None;
+
+    loop {
+        let read = match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(StreamError::Io(err))
+        };
+        let chunk = &chunk[..read];
+
+        let letters = match trailing {
+            Some(_) => 0,
+            None => chunk.iter().take_while(|unit| unit.is_ascii_alphabetic()).count()
+        };
+        reactions += react_onto(&mut stack, chunk[..letters].iter().cloned());
+        units_read += letters;
+
+        for (i, &unit) in chunk.iter().enumerate().skip(letters) {
+            let error = if unit.is_ascii_whitespace() {
+                trailing.get_or_insert((offset + i, unit as char));
+                continue;
+            } else if let (true, Some((offset, unit))) = (unit.is_ascii_alphabetic(), trailing) {
+                PolymerError::InvalidUnit { offset, unit }
+            } else {
+                // bytes outside of ascii may be split between chunks, so they aren't decoded
+                let unit = if unit.is_ascii() { unit as char } else { char::REPLACEMENT_CHARACTER };
+                PolymerError::InvalidUnit { offset: offset + i, unit }
+            };
+            return Err(StreamError::Polymer(error));
+        }
+        offset += read;
+    }
+
+    writer.write_all(&stack).map_err(StreamError::Io)?;
+    writer.flush().map_err(StreamError::Io)?;
+    Ok(StreamSummary { units_read, units: stack.len(), reactions })
+}
+
+pub fn reduce(input: &str) -> Result<String, PolymerError> {
+    react(input).map(|reaction| reaction.polymer)
+}
+
+pub fn reduce_and_measure(input: &str) -> Result<usize, PolymerError> {
+    react(input).map(|reaction| reaction.len())
+}
+
+pub fn cut_reduce_and_measure(input: &str) -> Result<usize, PolymerError> {
+    find_best_cut(input).map(|(_letter, length)| length)
+}
+
+// returns unit type which removal gives the shortest polymer and that length
+pub fn find_best_cut(input: &str) -> Result<(char, usize), PolymerError> {
+    rank_cuts(input).map(|ranking| ranking[0])
+}
+
+// every unit type with the polymer length after its removal, shortest first.
+// removal commutes with reduction, so candidates start from the reduced polymer
+// and each of them is reacted on its own thread
+pub fn rank_cuts(input: &str) -> Result<Vec<(char, usize)>, PolymerError> {
+    let reduced = reduce(input)?;
+    let units = reduced.as_bytes();
+
+    let mut ranking: Vec<(char, usize)> = thread::scope(|scope| {
+        let workers: Vec<_> = (b'a'..=b'z')
+            .map(|letter| scope.spawn(move || {
+                let remaining = units
+                    .iter()
+                    .cloned()
+                    .filter(|unit| unit.to_ascii_lowercase() != letter);
+                let (stack, _reactions) = react_units(remaining);
+                (char::from(letter), stack.len())
+            }))
             .collect();
-        let length = reduce_and_measure(&new_polymer);
-        if length < min_length { min_length = length; }
+        workers
+            .into_iter()
+            .map(|worker| worker.join().unwrap())
+            .collect()
+    });
+
+    ranking.sort_by_key(|&(letter, length)| (length, letter));
+    Ok(ranking)
+}
+
+pub struct Solver;
+impl Solution for Solver {
+    fn part_1(&self, input: &str) -> Option<String> {
+        Some(reduce_and_measure(input).unwrap().to_string())
+    }
+
+    fn part_2(&self, input: &str) -> Option<String> {
+        Some(cut_reduce_and_measure(input).unwrap().to_string())
+    }
+
+    fn explain(&self, input: &str, trace: &mut Trace) {
+        let reaction = react(input).unwrap();
+        trace.record("input units", input.trim_end().len());
+        trace.record("reactions", reaction.reactions);
+        trace.record("units after full reaction", reaction.len());
+        let ranking = rank_cuts(input).unwrap();
+        trace.record("best unit type to remove", ranking[0].0);
+        trace.record("units after its removal", ranking[0].1);
+        trace.record("runner-up", format!("{} ({} units)", ranking[1].0, ranking[1].1));
     }
-    min_length
 }
 
 #[test]
@@ -50,8 +341,85 @@ fn test_example() {
     let test_data = "dabAcCaCBAcCcaDA";
 
     let polymer_length = reduce_and_measure(test_data);
-    assert_eq!(polymer_length, 10);
+    assert_eq!(polymer_length, Ok(10));
 
     let min_polymer_length = cut_reduce_and_measure(test_data);
-    assert_eq!(min_polymer_length, 4);
+    assert_eq!(min_polymer_length, Ok(4));
+
+    assert_eq!(find_best_cut(test_data), Ok(('c', 4)));
+}
+
+#[test]
+fn test_ranking() {
+    let ranking = rank_cuts("dabAcCaCBAcCcaDA").unwrap();
+    assert_eq!(ranking.len(), 26);
+    assert_eq!(&ranking[..4], &[('c', 4), ('a', 6), ('d', 6), ('b', 8)][..]);
+    assert_eq!(ranking[4], ('e', 10));
+}
+
+#[test]
+fn test_reaction() {
+    let expected = Reaction { polymer: String::from("dabCBAcaDA"), units: 10, reactions: 3 };
+    assert_eq!(react("dabAcCaCBAcCcaDA\n"), Ok(expected));
+
+    assert_eq!(reduce("aA"), Ok(String::new()));
+    assert_eq!(reduce("abBA"), Ok(String::new()));
+    assert_eq!(reduce("abAB"), Ok(String::from("abAB")));
+    assert_eq!(reduce("aabAAB"), Ok(String::from("aabAAB")));
+}
+
+#[test]
+fn test_invalid_units() {
+    assert_eq!(react("ab cd"), Err(PolymerError::InvalidUnit { offset: 2, unit: ' ' }));
+    assert_eq!(react("aé"), Err(PolymerError::InvalidUnit { offset: 1, unit: 'é' }));
+    assert_eq!(reduce_and_measure("a1"), Err(PolymerError::InvalidUnit { offset: 1, unit: '1' }));
+}
+
+#[test]
+fn test_custom_rules() {
+    let mut rules = ReactionRules::table();
+    rules.add_pair("a", "b");
+    rules.add_unit("c");
+    assert_eq!(rules.react("cabbac").unwrap().polymer, "cc");
+    assert_eq!(rules.react("baab").unwrap().reactions, 2);
+    assert_eq!(rules.react("aA"), Err(PolymerError::InvalidUnit { offset: 1, unit: 'A' }));
+
+    // multi-character tokens, the longest one is matched first
+    let rules = ReactionRules::parse_str("# charges\n+1 -1\n+ -\n\n0\n").unwrap();
+    let reaction = rules.react("+1+-0-1+1-1\n").unwrap();
+    assert_eq!(reaction.polymer, "+10-1");
+    assert_eq!(reaction.units, 3);
+    assert_eq!(reaction.reactions, 2);
+
+    // table equal to the puzzle rule gives the same result
+    let text: String = (b'a'..=b'z')
+        .map(|letter| format!("{} {}\n", letter as char, letter.to_ascii_uppercase() as char))
+        .collect();
+    let rules = ReactionRules::parse_str(&text).unwrap();
+    assert_eq!(rules.react("dabAcCaCBAcCcaDA"), react("dabAcCaCBAcCcaDA"));
+
+    assert_eq!(
+        ReactionRules::parse_str("a A\na b c"),
+        Err(RulesError { line: 2, content: String::from("a b c") })
+    );
+}
+
+#[test]
+fn test_stream() {
+    let mut output = Vec::new();
+    let summary = reduce_stream("dabAcCaCBAcCcaDA\n".as_bytes(), &mut output).unwrap();
+    assert_eq!(output, b"dabCBAcaDA");
+    assert_eq!(summary, StreamSummary { units_read: 16, units: 10, reactions: 3 });
+
+    // reactions span chunk boundaries
+    let polymer = "ab".repeat(CHUNK_SIZE) + &"BA".repeat(CHUNK_SIZE) + "c";
+    let mut output = Vec::new();
+    let summary = reduce_stream(polymer.as_bytes(), &mut output).unwrap();
+    assert_eq!(output, b"c");
+    assert_eq!(summary.reactions, 2 * CHUNK_SIZE);
+
+    let error = reduce_stream("ab cd".as_bytes(), io::sink()).unwrap_err();
+    assert!(matches!(error, StreamError::Polymer(PolymerError::InvalidUnit { offset: 2, unit: ' ' })));
+    let error = reduce_stream("abé".as_bytes(), io::sink()).unwrap_err();
+    assert!(matches!(error, StreamError::Polymer(PolymerError::InvalidUnit { offset: 2, .. })));
 }
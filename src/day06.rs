@@ -1,11 +1,86 @@
-use std::collections::HashMap;
-use itertools::*;
+use std::collections::VecDeque;
+use std::fmt;
+use crate::solution::{Solution, Trace};
 
-#[derive(Debug)]
-enum Area {
-    Unknown,
-    Infinine,
-    Finite(u16)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Area {
+    Infinite,
+    Finite(usize)
+}
+impl fmt::Display for Area {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Area::Infinite => write!(f, "infinite"),
+            Area::Finite(size) => write!(f, "{}", size)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AreaReport {
+    pub label: String,
+    pub point: Point,
+    pub area: Area,
+    // place among the finite areas from the largest one, equal sizes share it
+    pub rank: Option<usize>
+}
+impl fmt::Display for AreaReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({}, {}): {}", self.label, self.point.x, self.point.y, self.area)?;
+        match self.rank {
+            Some(rank) => write!(f, ", rank {}", rank),
+            None => Ok(())
+        }
+    }
+}
+
+// only this many points can be told apart on the text map
+const MAP_LETTERS: usize = 26;
+
+// points are labelled in the order they were added, past `z` labels
+// go on like spreadsheet columns: `aa`, `ab`, ..., `zz`, `aaa`
+fn label_of(index: usize) -> String {
+    let mut label = Vec::new();
+    let mut n = index + 1;
+    while n > 0 {
+        n -= 1;
+        label.push(b'a' + (n % 26) as u8);
+        n /= 26;
+    }
+    label.reverse();
+    String::from_utf8(label).unwrap()
+}
+
+fn letter_of(index: usize) -> char {
+    (b'a' + index as u8) as char
+}
+
+// hues spread by the golden angle, so neighbouring indices look different
+fn colour_of(index: usize) -> [u8; 3] {
+    let hue = (index as f64 * 137.508) % 360.0 / 60.0;
+    let (saturation, value) = (0.6, 0.9);
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x)
+    };
+    let m = value - chroma;
+    let channel = |c: f64| ((c + m) * 255.0).round() as u8;
+    [channel(r), channel(g), channel(b)]
+}
+
+// label of a cell of the plot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    Unreached,
+    // index of the only closest point
+    Closest(usize),
+    Tie
 }
 
 #[derive(Debug, Hash, PartialEq, Eq, Copy, Clone)]
@@ -29,129 +104,606 @@ impl Point {
     }
 }
 
-pub struct Cartesian {
-    points: HashMap<Point, Area>,
+// how areas going on forever are recognised for a metric
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unbounded {
+    // whoever owns a cell on the border this far past the points
+    // owns every cell further out in the same direction
+    PastMargin(i32),
+    // exactly the points on the boundary of the convex hull
+    OnConvexHull
+}
+
+pub trait Metric {
+    fn distance(&self, from: &Point, to: &Point) -> i64;
+
+    // weights of the axes, if distance is the weighted sum of coordinate differences
+    fn axis_weights(&self) -> Option<(i64, i64)> { None }
+
+    // moves to neighbouring cells with their costs, if distance is the cheapest
+    // path made of them, which lets the plot be labelled by flood fill
+    fn steps(&self) -> Option<Vec<(i32, i32, i64)>> {
+        self.axis_weights().map(|(x, y)| vec![(-1, 0, x), (1, 0, x), (0, -1, y), (0, 1, y)])
+    }
+
+    // width and height are the extents of the points
+    fn unbounded(&self, width: i32, height: i32) -> Unbounded;
+}
+
+pub struct Manhattan;
+impl Metric for Manhattan {
+    fn distance(&self, from: &Point, to: &Point) -> i64 {
+        i64::from(from.manhattan_distance(to))
+    }
+
+    fn axis_weights(&self) -> Option<(i64, i64)> { Some((1, 1)) }
+
+    fn unbounded(&self, _width: i32, _height: i32) -> Unbounded { Unbounded::PastMargin(1) }
+}
+
+pub struct WeightedManhattan {
+    x: i64,
+    y: i64
+}
+impl WeightedManhattan {
+    pub fn new(x: i64, y: i64) -> Self {
+        assert!(x > 0 && y > 0, "axis weights must be positive");
+        WeightedManhattan { x, y }
+    }
+}
+impl Metric for WeightedManhattan {
+    fn distance(&self, from: &Point, to: &Point) -> i64 {
+        self.x * i64::from((from.x - to.x).abs()) + self.y * i64::from((from.y - to.y).abs())
+    }
+
+    fn axis_weights(&self) -> Option<(i64, i64)> { Some((self.x, self.y)) }
+
+    fn unbounded(&self, _width: i32, _height: i32) -> Unbounded { Unbounded::PastMargin(1) }
+}
+
+pub struct Chebyshev;
+impl Metric for Chebyshev {
+    fn distance(&self, from: &Point, to: &Point) -> i64 {
+        i64::from((from.x - to.x).abs().max((from.y - to.y).abs()))
+    }
+
+    fn steps(&self) -> Option<Vec<(i32, i32, i64)>> {
+        let moves = (-1..=1).flat_map(|dx| (-1..=1).map(move |dy| (dx, dy, 1)));
+        Some(moves.filter(|&(dx, dy, _)| dx != 0 || dy != 0).collect())
+    }
+
+    // once the cell is as far from the points along one axis as they spread
+    // along the other, moving outward adds the same to every distance
+    fn unbounded(&self, width: i32, height: i32) -> Unbounded {
+        Unbounded::PastMargin(width.max(height).max(1))
+    }
+}
+
+// areas are the same as for euclidean distance, but stay in integers
+pub struct SquaredEuclidean;
+impl Metric for SquaredEuclidean {
+    fn distance(&self, from: &Point, to: &Point) -> i64 {
+        let (dx, dy) = (i64::from(from.x - to.x), i64::from(from.y - to.y));
+        dx * dx + dy * dy
+    }
+
+    fn unbounded(&self, _width: i32, _height: i32) -> Unbounded { Unbounded::OnConvexHull }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlotError {
+    Empty,
+    AllInfinite,
+    // the same point again would tie with itself everywhere
+    DuplicatePoint(Point),
+    // text map has a single letter per point
+    TooManyPointsForMap(usize)
+}
+impl fmt::Display for PlotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlotError::Empty => write!(f, "plot has no points"),
+            PlotError::AllInfinite => write!(f, "every area of the plot is infinite"),
+            PlotError::DuplicatePoint(point) =>
+                write!(f, "point ({}, {}) is already on the plot", point.x, point.y),
+            PlotError::TooManyPointsForMap(count) =>
+                write!(f, "{} points can't be lettered on the map, at most {} can", count, MAP_LETTERS)
+        }
+    }
+}
+
+// exact bounds of the points, inclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bounds {
+    pub x_min: i32,
+    pub x_max: i32,
+    pub y_min: i32,
+    pub y_max: i32
+}
+impl Bounds {
+    fn around(point: &Point) -> Self {
+        Bounds { x_min: point.x, x_max: point.x, y_min: point.y, y_max: point.y }
+    }
+
+    fn include(&mut self, point: &Point) {
+        self.x_min = self.x_min.min(point.x);
+        self.x_max = self.x_max.max(point.x);
+        self.y_min = self.y_min.min(point.y);
+        self.y_max = self.y_max.max(point.y);
+    }
+
+    pub fn width(&self) -> i32 {
+        self.x_max - self.x_min
+    }
+
+    pub fn height(&self) -> i32 {
+        self.y_max - self.y_min
+    }
+
+    // cells of the bounds with `margin` more of them on every side
+    fn padded(&self, margin: i32) -> Grid {
+        Grid {
+            x_left: self.x_min - margin,
+            y_top: self.y_min - margin,
+            width: (self.width() + 2 * margin + 1) as usize,
+            height: (self.height() + 2 * margin + 1) as usize
+        }
+    }
+}
+
+// cells of the plot around the points, stored in row-major order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Grid {
     x_left: i32,
-    x_right: i32,
     y_top: i32,
-    y_bottom: i32
+    width: usize,
+    height: usize
+}
+impl Grid {
+    fn len(&self) -> usize {
+        self.width * self.height
+    }
+
+    fn index_of(&self, point: &Point) -> usize {
+        (point.y - self.y_top) as usize * self.width + (point.x - self.x_left) as usize
+    }
+
+    fn point_at(&self, index: usize) -> Point {
+        Point {
+            x: self.x_left + (index % self.width) as i32,
+            y: self.y_top + (index / self.width) as i32
+        }
+    }
+
+    fn neighbour(&self, index: usize, dx: i32, dy: i32) -> Option<usize> {
+        let x = (index % self.width) as i32 + dx;
+        let y = (index / self.width) as i32 + dy;
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return None;
+        }
+        Some(y as usize * self.width + x as usize)
+    }
+
+    fn is_border(&self, index: usize) -> bool {
+        let (x, y) = (index % self.width, index / self.width);
+        x == 0 || y == 0 || x + 1 == self.width || y + 1 == self.height
+    }
+}
+
+pub struct Cartesian {
+    points: Vec<Point>,
+    metric: Box<dyn Metric>,
+    // none until the first point is added
+    bounds: Option<Bounds>
+}
+impl Default for Cartesian {
+    fn default() -> Self {
+        Cartesian::with_metric(Manhattan)
+    }
 }
 impl Cartesian {
     pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_metric<M: Metric + 'static>(metric: M) -> Self {
         Cartesian {
-            points: HashMap::new(),
-            x_left: std::i32::MAX,
-            x_right: std::i32::MIN,
-            y_top: std::i32::MAX,
-            y_bottom: std::i32::MIN
+            points: Vec::new(),
+            metric: Box::new(metric),
+            bounds: None
         }
     }
 
-    pub fn add_point(&mut self, point: Point) {
-        if point.x < self.x_left { self.x_left = point.x - 1 }
-        if point.x > self.x_right { self.x_right = point.x + 1 }
-        if point.y < self.y_top { self.y_top = point.y - 1 }
-        if point.y > self.y_bottom { self.y_bottom = point.y + 1 }
-        self.points.insert(point, Area::Unknown);
+    // duplicates are rejected and leave the plot as it was
+    pub fn add_point(&mut self, point: Point) -> Result<(), PlotError> {
+        if self.points.contains(&point) {
+            return Err(PlotError::DuplicatePoint(point));
+        }
+        match &mut self.bounds {
+            Some(bounds) => bounds.include(&point),
+            None => self.bounds = Some(Bounds::around(&point))
+        }
+        self.points.push(point);
+        Ok(())
     }
 
-    pub fn find_largest_area(&mut self) -> u16 {
-        self.mark_infinite_areas();
-        self.calculate_finite_areas();
-        self.get_largest_area()
+    pub fn bounds(&self) -> Option<Bounds> {
+        self.bounds
     }
 
-    pub fn find_size_of_sweet_region(&self, bound: i32) -> u32 {
-        let mut size = 0;
-        for x in self.x_left..=self.x_right {
-            for y in self.y_top..=self.y_bottom {
-                let p = Point { x, y };
-                let mut total_distance = 0;
-                for input_point in self.points.keys() {
-                    total_distance += p.manhattan_distance(input_point);
-                }
-                if total_distance < bound {
-                    size += 1;
+    pub fn find_largest_area(&self) -> Result<usize, PlotError> {
+        let bounds = self.bounds.ok_or(PlotError::Empty)?;
+        self.calculate_areas(&bounds)
+        .into_iter()
+        .filter_map(|area| match area {
+            Area::Finite(size) => Some(size),
+            Area::Infinite => None
+        })
+        .max()
+        .ok_or(PlotError::AllInfinite)
+    }
+
+    // every point in the order it was added
+    pub fn area_report(&self) -> Result<Vec<AreaReport>, PlotError> {
+        let bounds = self.bounds.ok_or(PlotError::Empty)?;
+        let areas = self.calculate_areas(&bounds);
+        let sizes: Vec<usize> = areas
+            .iter()
+            .filter_map(|area| match area {
+                Area::Finite(size) => Some(*size),
+                Area::Infinite => None
+            })
+            .collect();
+
+        let report = self.points
+            .iter()
+            .zip(areas)
+            .enumerate()
+            .map(|(i, (&point, area))| AreaReport {
+                label: label_of(i),
+                point,
+                area,
+                rank: match area {
+                    Area::Finite(size) => Some(sizes.iter().filter(|&&other| other > size).count() + 1),
+                    Area::Infinite => None
                 }
+            })
+            .collect();
+        Ok(report)
+    }
+
+    // map of the bounding box padded by one as drawn in the puzzle, points are
+    // uppercase letters, their areas lowercase ones and ties are dots
+    pub fn render_map(&self) -> Result<String, PlotError> {
+        let grid = self.bounds.ok_or(PlotError::Empty)?.padded(1);
+        if self.points.len() > MAP_LETTERS {
+            return Err(PlotError::TooManyPointsForMap(self.points.len()));
+        }
+        let labels = self.label_cells(&grid);
+        let mut map = String::with_capacity(grid.len() + grid.height);
+
+        for (index, label) in labels.into_iter().enumerate() {
+            map.push(match label {
+                Cell::Closest(i) if self.points[i] == grid.point_at(index) => letter_of(i).to_ascii_uppercase(),
+                Cell::Closest(i) => letter_of(i),
+                _ => '.'
+            });
+            if (index + 1) % grid.width == 0 {
+                map.push('\n');
             }
         }
-        size
+        Ok(map)
     }
 
-    fn mark_infinite_areas(&mut self) {
-        let border_points = self.generate_border_points();
-        
-        for p in border_points {
-            if let Some(closest) = self.find_the_closest_point(&p) {
-                *self.points.get_mut(&closest).unwrap() = Area::Infinine;
+    // the same map as binary PPM image with a pixel per cell, areas are coloured,
+    // points are black and ties are white
+    pub fn render_ppm(&self) -> Result<Vec<u8>, PlotError> {
+        let grid = self.bounds.ok_or(PlotError::Empty)?.padded(1);
+        let labels = self.label_cells(&grid);
+        let mut image = format!("P6\n{} {}\n255\n", grid.width, grid.height).into_bytes();
+
+        for (index, label) in labels.into_iter().enumerate() {
+            image.extend_from_slice(&match label {
+                Cell::Closest(i) if self.points[i] == grid.point_at(index) => [0, 0, 0],
+                Cell::Closest(i) => colour_of(i),
+                _ => [255, 255, 255]
+            });
+        }
+        Ok(image)
+    }
+
+    // past the points every distance only grows further out, so once no cell
+    // on the border of the grid is within the bound, none outside of it is either
+    pub fn find_size_of_sweet_region(&self, bound: i32) -> Result<usize, PlotError> {
+        let bounds = self.bounds.ok_or(PlotError::Empty)?;
+        let mut margin = 1;
+        loop {
+            let (size, reaches_border) = self.measure_sweet_region(&bounds.padded(margin), i64::from(bound));
+            if !reaches_border {
+                return Ok(size);
             }
+            margin *= 2;
         }
     }
 
-    // this function generates minimal size rectangle border around points at plot
-    fn generate_border_points(&self) -> Vec<Point> {
-        (self.x_left..self.x_right+1)
-        .map(|n| Point{ x: n, y: self.y_top })
-        .chain(
-            (self.y_top+1..self.y_bottom+1)
-            .map(|n| Point{ x: self.x_right, y: n })
-        )
-        .chain(
-            (self.x_left..self.x_right)
-            .rev()
-            .map(|n| Point { x: n, y: self.y_bottom })
-        )
-        .chain((self.y_top+1..self.y_bottom)
-            .rev()
-            .map(|n| Point{ x: self.x_left, y: n })
-        )
-        .collect()
+    // size of the region within the grid and whether it reaches the border
+    fn measure_sweet_region(&self, grid: &Grid, bound: i64) -> (usize, bool) {
+        // sum of distances splits into independent sums along each axis,
+        // so every column and row is measured once and the cells are counted
+        // by looking up how many rows fit into what the column leaves of the bound
+        if let Some((x_weight, y_weight)) = self.metric.axis_weights() {
+            let x_right = grid.x_left + grid.width as i32 - 1;
+            let y_bottom = grid.y_top + grid.height as i32 - 1;
+            let columns: Vec<i64> = axis_distance_sums(self.points.iter().map(|p| p.x), grid.x_left, x_right)
+                .into_iter()
+                .map(|sum| x_weight * sum)
+                .collect();
+            let rows: Vec<i64> = axis_distance_sums(self.points.iter().map(|p| p.y), grid.y_top, y_bottom)
+                .into_iter()
+                .map(|sum| y_weight * sum)
+                .collect();
+
+            let (closest_column, closest_row) = (columns.iter().min().unwrap(), rows.iter().min().unwrap());
+            let reaches_border =
+                columns[0] + closest_row < bound || columns[columns.len() - 1] + closest_row < bound ||
+                rows[0] + closest_column < bound || rows[rows.len() - 1] + closest_column < bound;
+
+            let mut rows = rows;
+            rows.sort_unstable();
+            let size = columns
+                .into_iter()
+                .map(|column| rows.partition_point(|&row| column + row < bound))
+                .sum();
+            return (size, reaches_border);
+        }
+
+        let mut size = 0;
+        let mut reaches_border = false;
+        for index in 0..grid.len() {
+            let cell = grid.point_at(index);
+            if self.points.iter().map(|p| self.metric.distance(p, &cell)).sum::<i64>() < bound {
+                size += 1;
+                reaches_border |= grid.is_border(index);
+            }
+        }
+        (size, reaches_border)
     }
 
-    // this function gives closest point if only one such point exists
-    fn find_the_closest_point(&self, point: &Point) -> Option<Point> {
-        let distances: Vec<_> =
-            self.points.keys()
-            .map(|p| (p, p.manhattan_distance(&point)))
-            .sorted_by_key(|t| t.1)
-            .collect();
+    fn label_cells(&self, grid: &Grid) -> Vec<Cell> {
+        match self.metric.steps() {
+            Some(steps) => self.flood_fill(grid, &steps),
+            None => self.scan_cells(grid)
+        }
+    }
+
+    // multi-source flood fill visiting cells in the order of their distance.
+    // equal step costs make it a plain breadth first search, otherwise cells wait
+    // in a circular bucket queue with a bucket per distance up to the largest cost
+    // ahead. distance is the cheapest path on the grid, so the closest points of
+    // a cell are exactly those of its neighbours lying on such paths, any
+    // disagreement is a tie
+    fn flood_fill(&self, grid: &Grid, steps: &[(i32, i32, i64)]) -> Vec<Cell> {
+        let mut labels = vec![Cell::Unreached; grid.len()];
+        let mut distances = vec![i64::MAX; grid.len()];
+        let mut sources = Vec::with_capacity(self.points.len());
 
-        match distances.len() {
-            0 => panic!("add some points to plot first!"),
-            1 => Some(*distances[0].0),
-            _ => {
-                let (p1, d1) = distances[0];
-                let (__, d2) = distances[1];
-                if d1 == d2 { return None; }
-                else { return Some(*p1); }
+        for (i, point) in self.points.iter().enumerate() {
+            let index = grid.index_of(point);
+            labels[index] = Cell::Closest(i);
+            distances[index] = 0;
+            sources.push(index);
+        }
+
+        let uniform = steps.iter().all(|&(_, _, cost)| cost == steps[0].2);
+        if uniform {
+            let mut queue: VecDeque<usize> = sources.into_iter().collect();
+            while let Some(index) = queue.pop_front() {
+                relax(grid, steps, index, &mut labels, &mut distances, |_, next| queue.push_back(next));
             }
+            return labels;
         }
+
+        let largest = steps.iter().map(|&(_, _, cost)| cost).max().unwrap();
+        let mut buckets: Vec<Vec<(i64, usize)>> = vec![Vec::new(); largest as usize + 1];
+        let slots = buckets.len() as i64;
+        let mut pending = sources.len();
+        buckets[0] = sources.into_iter().map(|index| (0, index)).collect();
+
+        let mut distance = 0;
+        while pending > 0 {
+            let slot = (distance % slots) as usize;
+            while let Some((queued, index)) = buckets[slot].pop() {
+                pending -= 1;
+                // cell was reached cheaper after this entry had been queued
+                if queued > distances[index] { continue }
+
+                relax(grid, steps, index, &mut labels, &mut distances, |through, next| {
+                    buckets[(through % slots) as usize].push((through, next));
+                    pending += 1;
+                });
+            }
+            distance += 1;
+        }
+        labels
     }
 
-    fn calculate_finite_areas(&mut self) {
-        for x in self.x_left..=self.x_right {
-            for y in self.y_top..=self.y_bottom {
-                let p = Point { x, y };
-                if let Some(closest) = self.find_the_closest_point(&p) {
-                    let area = self.points.get_mut(&closest).unwrap();
-                    match area {
-                        Area::Infinine => (),
-                        Area::Unknown => *area = Area::Finite(1),
-                        Area::Finite(x) => *area = Area::Finite(*x+1)
-                    }
+    fn scan_cells(&self, grid: &Grid) -> Vec<Cell> {
+        (0..grid.len())
+        .map(|index| {
+            let cell = grid.point_at(index);
+            let mut label = Cell::Unreached;
+            let mut closest = i64::MAX;
+            for (i, point) in self.points.iter().enumerate() {
+                let distance = self.metric.distance(point, &cell);
+                if distance < closest {
+                    closest = distance;
+                    label = Cell::Closest(i);
+                } else if distance == closest {
+                    label = Cell::Tie;
                 }
             }
+            label
+        })
+        .collect()
+    }
+
+    // sizes of the areas within the grid and whether they reach its border
+    fn measure_areas(&self, grid: &Grid) -> Vec<(usize, bool)> {
+        let mut areas = vec![(0, false); self.points.len()];
+        for (index, label) in self.label_cells(grid).into_iter().enumerate() {
+            if let Cell::Closest(i) = label {
+                areas[i].0 += 1;
+                areas[i].1 |= grid.is_border(index);
+            }
         }
+        areas
     }
 
-    fn get_largest_area(&self) -> u16 {
-        self.points.values()
-        .map(|v| match v {
-            Area::Finite(x) => *x,
-            _ => 0
-        })
-        .max()
-        .unwrap()
+    fn calculate_areas(&self, bounds: &Bounds) -> Vec<Area> {
+        let (mut margin, infinite) = match self.metric.unbounded(bounds.width(), bounds.height()) {
+            Unbounded::PastMargin(margin) => (margin.max(1), None),
+            Unbounded::OnConvexHull => (1, Some(self.on_convex_hull()))
+        };
+
+        loop {
+            let areas = self.measure_areas(&bounds.padded(margin));
+            let infinite = match &infinite {
+                Some(infinite) => infinite.clone(),
+                None => areas.iter().map(|&(_, on_border)| on_border).collect()
+            };
+            // finite areas may still stick out of the grid, it has to grow until they don't
+            let clipped = areas.iter().zip(&infinite).any(|(&(_, on_border), &infinite)| on_border && !infinite);
+            if clipped {
+                margin *= 2;
+                continue;
+            }
+
+            return areas
+                .into_iter()
+                .zip(infinite)
+                .map(|((size, _), infinite)| if infinite { Area::Infinite } else { Area::Finite(size) })
+                .collect();
+        }
+    }
+
+    // whether every point lies on the boundary of the convex hull, collinear ones included
+    fn on_convex_hull(&self) -> Vec<bool> {
+        let hull = convex_hull(&self.points);
+        if hull.len() < 3 {
+            return vec![true; self.points.len()];
+        }
+        self.points
+        .iter()
+        .map(|p| (0..hull.len()).any(|i| {
+            let (a, b) = (hull[i], hull[(i + 1) % hull.len()]);
+            cross(&a, &b, p) == 0 &&
+            p.x >= a.x.min(b.x) && p.x <= a.x.max(b.x) &&
+            p.y >= a.y.min(b.y) && p.y <= a.y.max(b.y)
+        }))
+        .collect()
+    }
+}
+
+fn cross(origin: &Point, a: &Point, b: &Point) -> i64 {
+    i64::from(a.x - origin.x) * i64::from(b.y - origin.y) -
+    i64::from(a.y - origin.y) * i64::from(b.x - origin.x)
+}
+
+// vertices of the convex hull in counter-clockwise order, without collinear ones
+fn convex_hull(points: &[Point]) -> Vec<Point> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by_key(|p| (p.x, p.y));
+
+    let mut hull: Vec<Point> = Vec::new();
+    for pass in 0..2 {
+        let start = hull.len();
+        for p in &sorted {
+            while hull.len() >= start + 2 && cross(&hull[hull.len() - 2], &hull[hull.len() - 1], p) <= 0 {
+                hull.pop();
+            }
+            hull.push(*p);
+        }
+        hull.pop();
+        if pass == 0 {
+            sorted.reverse();
+        }
+    }
+    hull
+}
+
+// offers neighbours of the cell a path through it, `queue` gets the ones
+// which got closer with their new distance
+fn relax<F: FnMut(i64, usize)>(
+    grid: &Grid,
+    steps: &[(i32, i32, i64)],
+    index: usize,
+    labels: &mut [Cell],
+    distances: &mut [i64],
+    mut queue: F
+) {
+    for &(dx, dy, cost) in steps {
+        let next = match grid.neighbour(index, dx, dy) {
+            Some(next) => next,
+            None => continue
+        };
+        let through = distances[index] + cost;
+        if through < distances[next] {
+            distances[next] = through;
+            labels[next] = labels[index];
+            queue(through, next);
+        } else if through == distances[next] && labels[next] != labels[index] {
+            labels[next] = Cell::Tie;
+        }
+    }
+}
+
+// sum of distances from every coordinate in `from..=to` to all the given ones,
+// walking the range in order with prefix sums of the sorted coordinates
+fn axis_distance_sums<I: Iterator<Item = i32>>(coordinates: I, from: i32, to: i32) -> Vec<i64> {
+    let mut coordinates: Vec<i64> = coordinates.map(i64::from).collect();
+    coordinates.sort_unstable();
+    let total: i64 = coordinates.iter().sum();
+    let count = coordinates.len() as i64;
+
+    let mut before = 0;
+    let mut before_sum = 0;
+    (from..=to)
+    .map(i64::from)
+    .map(|at| {
+        while before < coordinates.len() && coordinates[before] <= at {
+            before_sum += coordinates[before];
+            before += 1;
+        }
+        let below = before as i64;
+        (below * at - before_sum) + (total - before_sum - (count - below) * at)
+    })
+    .collect()
+}
+
+pub struct Solver;
+impl Solver {
+    fn plot(input: &str) -> Cartesian {
+        let mut plot = Cartesian::new();
+        for point in input.lines().map(Point::parse_str) {
+            plot.add_point(point).unwrap();
+        }
+        plot
+    }
+}
+impl Solution for Solver {
+    fn part_1(&self, input: &str) -> Option<String> {
+        Some(Self::plot(input).find_largest_area().unwrap().to_string())
+    }
+
+    fn part_2(&self, input: &str) -> Option<String> {
+        Some(Self::plot(input).find_size_of_sweet_region(10000).unwrap().to_string())
+    }
+
+    fn explain(&self, input: &str, trace: &mut Trace) {
+        let areas = trace.section("areas");
+        for report in Self::plot(input).area_report().unwrap() {
+            areas.record("point", report);
+        }
     }
 }
 
@@ -167,11 +719,161 @@ fn test_example() {
     let mut plot = Cartesian::new();
     
     for raw_point in test_data.lines() {
-        plot.add_point(Point::parse_str(raw_point));
+        plot.add_point(Point::parse_str(raw_point)).unwrap();
+    }
+
+    assert_eq!(plot.find_largest_area(), Ok(17));
+    assert_eq!(plot.find_size_of_sweet_region(32), Ok(16));
+}
+
+#[cfg(test)]
+fn check_against_brute_force(mut plot: Cartesian) {
+    for &(x, y) in [(3, 2), (9, 4), (1, 8), (6, 6), (12, 11), (4, 12), (10, 1), (7, 9)].iter() {
+        plot.add_point(Point { x, y }).unwrap();
+    }
+
+    let grid = plot.bounds().unwrap().padded(3);
+    assert_eq!(plot.label_cells(&grid), plot.scan_cells(&grid));
+
+    // far enough that every finite area fits in and the infinite ones reach the border
+    let wide = plot.bounds().unwrap().padded(60);
+    let labels = plot.scan_cells(&wide);
+    let expected: Vec<Area> = plot.points
+        .iter()
+        .enumerate()
+        .map(|(i, _)| {
+            let cells: Vec<usize> = (0..wide.len()).filter(|&index| labels[index] == Cell::Closest(i)).collect();
+            match cells.iter().any(|&index| wide.is_border(index)) {
+                true => Area::Infinite,
+                false => Area::Finite(cells.len())
+            }
+        })
+        .collect();
+    assert_eq!(plot.calculate_areas(&plot.bounds().unwrap()), expected);
+
+    for &bound in [20, 40, 60, 80, 200, 400].iter() {
+        let (size, reaches_border) = plot.measure_sweet_region(&wide, bound);
+        assert!(!reaches_border);
+        assert_eq!(plot.find_size_of_sweet_region(bound as i32), Ok(size));
+    }
+}
+
+#[test]
+fn test_metrics() {
+    check_against_brute_force(Cartesian::new());
+    check_against_brute_force(Cartesian::with_metric(WeightedManhattan::new(2, 3)));
+    check_against_brute_force(Cartesian::with_metric(WeightedManhattan::new(200_000, 1)));
+    check_against_brute_force(Cartesian::with_metric(Chebyshev));
+    check_against_brute_force(Cartesian::with_metric(SquaredEuclidean));
+}
+
+#[test]
+fn test_region_beyond_bounding_box() {
+    // around a single point the region is a ball of the metric
+    let plots: Vec<(Cartesian, usize)> = vec![
+        (Cartesian::new(), 2 * 9 * 9 + 2 * 9 + 1),
+        (Cartesian::with_metric(Chebyshev), 19 * 19),
+        (Cartesian::with_metric(SquaredEuclidean), 29)
+    ];
+    for (mut plot, size) in plots {
+        plot.add_point(Point { x: 5, y: -3 }).unwrap();
+        assert_eq!(plot.find_size_of_sweet_region(10), Ok(size));
+    }
+
+    let mut plot = Cartesian::new();
+    for &(x, y) in [(1, 1), (1, 6), (8, 3), (3, 4), (5, 5), (8, 9)].iter() {
+        plot.add_point(Point { x, y }).unwrap();
+    }
+    let (inside_box, _) = plot.measure_sweet_region(&plot.bounds().unwrap().padded(1), 200);
+    let (size, reaches_border) = plot.measure_sweet_region(&plot.bounds().unwrap().padded(40), 200);
+    assert!(!reaches_border && size > inside_box);
+    assert_eq!(plot.find_size_of_sweet_region(200), Ok(size));
+}
+
+#[test]
+fn test_convex_hull() {
+    let mut plot = Cartesian::with_metric(SquaredEuclidean);
+    for &(x, y) in [(0, 0), (4, 0), (2, 0), (4, 4), (0, 4), (2, 2), (1, 3)].iter() {
+        plot.add_point(Point { x, y }).unwrap();
+    }
+    assert_eq!(plot.on_convex_hull(), vec![true, true, true, true, true, false, false]);
+
+    let mut line = Cartesian::with_metric(SquaredEuclidean);
+    for &(x, y) in [(0, 0), (1, 1), (3, 3)].iter() {
+        line.add_point(Point { x, y }).unwrap();
+    }
+    assert_eq!(line.on_convex_hull(), vec![true; 3]);
+}
+
+#[test]
+fn test_rendering() {
+    let mut plot = Cartesian::new();
+    for &(x, y) in [(1, 1), (1, 6), (8, 3), (3, 4), (5, 5), (8, 9)].iter() {
+        plot.add_point(Point { x, y }).unwrap();
+    }
+
+    let expected = "\
+aaaaa.cccc
+aAaaa.cccc
+aaaddecccc
+aadddeccCc
+..dDdeeccc
+bb.deEeecc
+bBb.eeee..
+bbb.eeefff
+bbb.eeffff
+bbb.ffffFf
+bbb.ffffff
+";
+    assert_eq!(plot.render_map().unwrap(), expected);
+
+    let image = plot.render_ppm().unwrap();
+    let header = b"P6\n10 11\n255\n";
+    assert_eq!(&image[..header.len()], &header[..]);
+    assert_eq!(image.len(), header.len() + 10 * 11 * 3);
+    // (1, 1) is the point a itself, (0, 0) belongs to its area and (5, 0) is a tie
+    assert_eq!(&image[header.len() + 3 * 11..][..3], &[0, 0, 0]);
+    assert_eq!(&image[header.len()..][..3], &colour_of(0));
+    assert_eq!(&image[header.len() + 3 * 5..][..3], &[255, 255, 255]);
+
+    let report: Vec<String> = plot.area_report().unwrap().iter().map(|report| report.to_string()).collect();
+    assert_eq!(report, vec![
+        "a (1, 1): infinite",
+        "b (1, 6): infinite",
+        "c (8, 3): infinite",
+        "d (3, 4): 9, rank 2",
+        "e (5, 5): 17, rank 1",
+        "f (8, 9): infinite"
+    ]);
+}
+
+#[test]
+fn test_degenerate_plots() {
+    let mut plot = Cartesian::new();
+    assert_eq!(plot.bounds(), None);
+    assert_eq!(plot.find_largest_area(), Err(PlotError::Empty));
+    assert_eq!(plot.find_size_of_sweet_region(10), Err(PlotError::Empty));
+    assert_eq!(plot.render_map(), Err(PlotError::Empty));
+
+    plot.add_point(Point { x: 2, y: 3 }).unwrap();
+    plot.add_point(Point { x: 5, y: -1 }).unwrap();
+    assert_eq!(plot.add_point(Point { x: 2, y: 3 }), Err(PlotError::DuplicatePoint(Point { x: 2, y: 3 })));
+    assert_eq!(plot.bounds(), Some(Bounds { x_min: 2, x_max: 5, y_min: -1, y_max: 3 }));
+    assert_eq!(plot.find_largest_area(), Err(PlotError::AllInfinite));
+    assert_eq!(plot.area_report().unwrap().len(), 2);
+}
+
+#[test]
+fn test_labels() {
+    let mut plot = Cartesian::new();
+    for i in 0..30 {
+        plot.add_point(Point { x: i % 6, y: i / 6 }).unwrap();
     }
+    assert_eq!(plot.render_map(), Err(PlotError::TooManyPointsForMap(30)));
 
-    assert_eq!(plot.find_largest_area(), 17);
-    assert_eq!(plot.find_size_of_sweet_region(32), 16);
+    let labels: Vec<String> = plot.area_report().unwrap().into_iter().map(|report| report.label).collect();
+    assert_eq!(&labels[24..], &["y", "z", "aa", "ab", "ac", "ad"]);
+    assert_eq!(label_of(26 + 26 * 26), "aaa");
 }
 
 #[test]
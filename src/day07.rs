@@ -1,4 +1,5 @@
 use std::collections::{HashMap, HashSet};
+use crate::solution::Solution;
 
 #[derive(Debug, PartialEq)]
 pub struct Rule {
@@ -39,75 +40,198 @@ impl Instructions {
         }
     }
 }
+impl Instructions {
+    // takes the first step in alphabetical order which has all prerequisites
+    // done, steps depending on it stay locked until it is completed
+    fn start_next(&mut self) -> Option<char> {
+        let step = (b'A'..=b'Z')
+            .map(char::from)
+            .find(|c| self.0.get(c).is_some_and(|set| set.is_empty()))?;
+        self.0.remove(&step);
+        Some(step)
+    }
+
+    fn complete(&mut self, step: char) {
+        for set in self.0.values_mut() {
+            set.remove(&step);
+        }
+    }
+}
 impl Iterator for Instructions {
     type Item = char;
 
     fn next(&mut self) -> Option<Self::Item> {
-        for c in (b'A'..=b'Z').map(char::from) {
-            if let Some(set) = self.0.get_mut(&c) {
-                if set.is_empty() {
-                    for val in self.0.values_mut() {
-                        val.remove(&c);
-                    }
-                    self.0.remove(&c);
-                    return Some(c);
-                }
-            } 
-        }
-        None
+        let step = self.start_next()?;
+        self.complete(step);
+        Some(step)
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Job {
+    step: char,
+    start: u32,
+    end: u32
+}
+
+// step was worked on during seconds `start..end`, workers are numbered from 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskRecord {
+    pub step: char,
+    pub start: u32,
+    pub end: u32,
+    pub worker: usize
+}
+
 #[derive(Debug)]
-struct Runtime {
+pub struct Runtime {
     instructions: Instructions,
-    workers: Vec<i32>,
-    time_spent: u16
+    workers: Vec<Option<Job>>,
+    // seconds every step takes on top of its letter number
+    base_duration: u32,
+    time_spent: u32,
+    // in the order the steps were started
+    records: Vec<TaskRecord>
 }
 impl Runtime {
-    pub fn new(instructions: Instructions, workers_amount: usize) -> Self {
-        let workers = std::iter::repeat(0).take(workers_amount).collect();
-        let time_spent = 0;
+    pub fn new(instructions: Instructions, workers_amount: usize, base_duration: u32) -> Self {
         Self {
-            instructions, workers, time_spent
+            instructions,
+            workers: vec![None; workers_amount],
+            base_duration,
+            time_spent: 0,
+            records: Vec::new()
         }
     }
 
-    pub fn process(&mut self) -> u16 {
+    // jumps from one finished job to the next one, releasing everything that
+    // finished at that second before idle workers pick up the next steps.
+    // None when some steps can never start, e.g. prerequisites are cyclic
+    pub fn process(&mut self) -> Option<u32> {
         loop {
-            println!("{:?}\n", &self);
-            let more_available = self.load_available_workers();
-            let more_ticks_required = self.tick();
-            if !more_available && !more_ticks_required { break; }
+            self.load_available_workers();
+
+            let next_end = self.workers.iter().flatten().map(|job| job.end).min();
+            match next_end {
+                Some(end) => self.finish_jobs(end),
+                None => break
+            }
         }
-        self.time_spent
+        if self.instructions.0.is_empty() { Some(self.time_spent) } else { None }
     }
 
-    // emulates quant of time, returns true if
-    // one or more workers still have work to do
-    fn tick(&mut self) -> bool {
-        for worker in &mut self.workers {
-            *worker -= 1;
+    pub fn records(&self) -> &[TaskRecord] {
+        &self.records
+    }
+
+    pub fn records_csv(&self) -> String {
+        let mut csv = String::from("step,start,end,worker\n");
+        for record in &self.records {
+            csv.push_str(&format!("{},{},{},{}\n", record.step, record.start, record.end, record.worker));
+        }
+        csv
+    }
+
+    // per second table of the processed schedule as laid out in the puzzle,
+    // every worker column shows its step or `.` and done lists finished steps
+    pub fn render_timeline(&self) -> String {
+        let mut header = String::from("Second");
+        let mut columns = Vec::new();
+        for worker in 1..=self.workers.len() {
+            header.push_str("   ");
+            columns.push(header.len() + 3);
+            header.push_str(&format!("Worker {}", worker));
         }
-        self.time_spent += 1;
-        self.workers.iter().any(|w| *w > 0)
+        header.push_str("   ");
+        let done_column = header.len();
+        header.push_str("Done");
+
+        let mut finished = self.records.clone();
+        finished.sort_by_key(|record| (record.end, record.step));
+
+        let mut timeline = header;
+        timeline.push('\n');
+        for second in 0..=self.time_spent {
+            let mut row = format!("{:>4}", second);
+            for (i, &column) in columns.iter().enumerate() {
+                let step = self.records
+                    .iter()
+                    .find(|record| record.worker == i + 1 && record.start <= second && second < record.end)
+                    .map_or('.', |record| record.step);
+                pad_to(&mut row, column);
+                row.push(step);
+            }
+            let done: String = finished
+                .iter()
+                .filter(|record| record.end <= second)
+                .map(|record| record.step)
+                .collect();
+            if !done.is_empty() {
+                pad_to(&mut row, done_column);
+                row.push_str(&done);
+            }
+            timeline.push_str(&row);
+            timeline.push('\n');
+        }
+        timeline
     }
 
-    fn seconds_required(&self, ch: char) -> i32 {
-        (ch as u8 - b'A' + 1) as i32
+    fn seconds_required(&self, ch: char) -> u32 {
+        self.base_duration + u32::from(ch as u8 - b'A' + 1)
     }
 
-    // fills all vacant workers with next task,
-    // returns true if more tasks in queue still awaiting
-    fn load_available_workers(&mut self) -> bool { // more_tasks_available
-        let mut is_more_tasks_in_queue = true;
-        for i in 0..self.workers.iter().filter(|w| **w <= 0).count() {
-            match self.instructions.next() {
-                Some(ch) => self.workers[i] = self.seconds_required(ch),
-                None => is_more_tasks_in_queue = false
+    // idle workers take available steps in alphabetical order
+    fn load_available_workers(&mut self) {
+        for i in 0..self.workers.len() {
+            if self.workers[i].is_some() { continue }
+            match self.instructions.start_next() {
+                Some(step) => {
+                    let start = self.time_spent;
+                    let end = start + self.seconds_required(step);
+                    self.workers[i] = Some(Job { step, start, end });
+                    self.records.push(TaskRecord { step, start, end, worker: i + 1 });
+                },
+                None => break
             }
         }
-        is_more_tasks_in_queue
+    }
+
+    fn finish_jobs(&mut self, time: u32) {
+        self.time_spent = time;
+        for worker in &mut self.workers {
+            if let Some(job) = *worker {
+                if job.end == time {
+                    self.instructions.complete(job.step);
+                    *worker = None;
+                }
+            }
+        }
+    }
+}
+
+// keeps at least one space even when the row already runs past the column
+fn pad_to(row: &mut String, column: usize) {
+    let width = column.saturating_sub(row.len()).max(1);
+    row.push_str(&" ".repeat(width));
+}
+
+pub struct Solver;
+impl Solver {
+    fn instructions(input: &str) -> Instructions {
+        let mut instructions = Instructions::new();
+        for rule in input.lines().map(Rule::parse_raw) {
+            instructions.add_rule(rule);
+        }
+        instructions
+    }
+}
+impl Solution for Solver {
+    fn part_1(&self, input: &str) -> Option<String> {
+        Some(Self::instructions(input).collect())
+    }
+
+    fn part_2(&self, input: &str) -> Option<String> {
+        Some(Runtime::new(Self::instructions(input), 5, 60).process()?.to_string())
     }
 }
 
@@ -132,10 +256,65 @@ Step F must be finished before step E can begin.";
         instructions.add_rule(rule);
     }
 
-    let mut runtime = Runtime::new(instructions.clone(), 2);
+    let mut runtime = Runtime::new(instructions.clone(), 2, 0);
 
     let order: String = instructions.collect();
     assert_eq!(order, "CABDFE");
 
-    assert_eq!(runtime.process(), 15);
-}
\ No newline at end of file
+    assert_eq!(runtime.process(), Some(15));
+
+    let expected = "\
+Second   Worker 1   Worker 2   Done
+   0        C          .
+   1        C          .
+   2        C          .
+   3        A          F       C
+   4        B          F       CA
+   5        B          F       CA
+   6        D          F       CAB
+   7        D          F       CAB
+   8        D          F       CAB
+   9        D          .       CABF
+  10        E          .       CABFD
+  11        E          .       CABFD
+  12        E          .       CABFD
+  13        E          .       CABFD
+  14        E          .       CABFD
+  15        .          .       CABFDE
+";
+    assert_eq!(runtime.render_timeline(), expected);
+
+    assert_eq!(runtime.records()[..3], [
+        TaskRecord { step: 'C', start: 0, end: 3, worker: 1 },
+        TaskRecord { step: 'A', start: 3, end: 4, worker: 1 },
+        TaskRecord { step: 'F', start: 3, end: 9, worker: 2 }
+    ]);
+    assert_eq!(runtime.records().len(), 6);
+    assert!(runtime.records_csv().starts_with("step,start,end,worker\nC,0,3,1\nA,3,4,1\n"));
+}
+
+#[test]
+fn test_base_duration() {
+    let mut instructions = Instructions::new();
+    instructions.add_rule(Rule::parse_raw("Step A must be finished before step C can begin."));
+    instructions.add_rule(Rule::parse_raw("Step B must be finished before step C can begin."));
+
+    // A and B run side by side, C has to wait for the longer B
+    assert_eq!(Runtime::new(instructions.clone(), 2, 60).process(), Some(62 + 63));
+    assert_eq!(Runtime::new(instructions.clone(), 1, 60).process(), Some(61 + 62 + 63));
+    assert_eq!(Runtime::new(instructions.clone(), 3, 0).process(), Some(2 + 3));
+    assert_eq!(Runtime::new(instructions, 0, 0).process(), None);
+}
+
+#[test]
+fn test_unsatisfiable_steps() {
+    let mut instructions = Instructions::new();
+    instructions.add_rule(Rule::parse_raw("Step A must be finished before step B can begin."));
+    instructions.add_rule(Rule::parse_raw("Step B must be finished before step C can begin."));
+    instructions.add_rule(Rule::parse_raw("Step C must be finished before step B can begin."));
+
+    // A runs, but B and C wait for each other forever
+    let mut runtime = Runtime::new(instructions, 2, 0);
+    assert_eq!(runtime.process(), None);
+    assert_eq!(runtime.records().len(), 1);
+}
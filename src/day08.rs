@@ -1,3 +1,5 @@
+use crate::solution::Solution;
+
 pub fn parse_string(raw: &str) -> Vec<usize> {
     raw.split(' ').map(|num| num.parse().unwrap()).collect()
 }
@@ -73,6 +75,17 @@ impl Tree {
     }
 }
 
+pub struct Solver;
+impl Solution for Solver {
+    fn part_1(&self, input: &str) -> Option<String> {
+        Some(Tree::new(parse_string(input)).first_check().to_string())
+    }
+
+    fn part_2(&self, input: &str) -> Option<String> {
+        Some(Tree::new(parse_string(input)).second_check().to_string())
+    }
+}
+
 #[test]
 fn test_parse_raw_string() {
     let test_data = "2 3 0 3 10 11 12 1 1 0 1 99 2 1 1 2";
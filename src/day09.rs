@@ -1,4 +1,5 @@
 use std::collections::{HashMap, VecDeque};
+use crate::solution::Solution;
 
 pub fn parse_input(raw: &str) -> (usize, usize) {
     let parts: Vec<_> = raw.split(' ').collect();
@@ -58,6 +59,19 @@ impl Game {
     }
 }
 
+pub struct Solver;
+impl Solution for Solver {
+    fn part_1(&self, input: &str) -> Option<String> {
+        let (total_players, last_marble) = parse_input(input);
+        Some(Game::new(total_players, last_marble).calculate_highscore().to_string())
+    }
+
+    fn part_2(&self, input: &str) -> Option<String> {
+        let (total_players, last_marble) = parse_input(input);
+        Some(Game::new(total_players, last_marble * 100).calculate_highscore().to_string())
+    }
+}
+
 #[test]
 fn test_marble_game_1() {
     let game = Game::new(10, 1618);
@@ -1,4 +1,5 @@
 use regex::Regex;
+use crate::solution::Solution;
 
 #[derive(Debug, PartialEq)]
 struct Position {
@@ -130,6 +131,24 @@ impl Sky {
     }
 }
 
+pub struct Solver;
+impl Solver {
+    fn sky(input: &str) -> Sky {
+        let mut sky = Sky::new(parse_points(input));
+        sky.await_some_time();
+        sky
+    }
+}
+impl Solution for Solver {
+    fn part_1(&self, input: &str) -> Option<String> {
+        Some(Self::sky(input).read_message())
+    }
+
+    fn part_2(&self, input: &str) -> Option<String> {
+        Some(Self::sky(input).get_timestamp().to_string())
+    }
+}
+
 #[test]
 fn test_parsing() {
     let test_input = r"position=< 52534, -31215> velocity=<-5,  3>
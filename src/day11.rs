@@ -1,3 +1,5 @@
+use crate::solution::Solution;
+
 const GRID_SIZE: i32 = 300;
 
 macro_rules! q {
@@ -108,6 +110,21 @@ impl PowerGrid {
     }
 }
 
+pub struct Solver;
+impl Solution for Solver {
+    fn part_1(&self, input: &str) -> Option<String> {
+        let grid = PowerGrid::new(input.trim().parse().unwrap());
+        let measure = grid.find_max_power(3);
+        Some(format!("{},{}", measure.x, measure.y))
+    }
+
+    fn part_2(&self, input: &str) -> Option<String> {
+        let grid = PowerGrid::new(input.trim().parse().unwrap());
+        let abs = grid.find_max_power_absolute();
+        Some(format!("{},{},{}", abs.x, abs.y, abs.size))
+    }
+}
+
 #[test]
 fn test_hundreds_extractor() {
     assert_eq!(PowerGrid::hundreds_extractor(12345), 3);
@@ -1,4 +1,5 @@
 use std::collections::{HashMap, HashSet};
+use crate::solution::Solution;
 
 #[derive(Debug, Eq, PartialEq)]
 pub enum TrackType {
@@ -195,6 +196,27 @@ pub fn parse_input(raw: &str) -> (HashMap<Coordinate, TrackType>, Vec<Cart>) {
     (tracks, carts)
 }
 
+pub struct Solver;
+impl Solution for Solver {
+    fn part_1(&self, input: &str) -> Option<String> {
+        let (tracks, carts) = parse_input(input);
+        // a single cart never crashes, ticking would go on forever
+        if carts.len() < 2 {
+            return None;
+        }
+        let mut map = Map::new(tracks, carts);
+        loop {
+            if let Some(coord) = map.tick() {
+                return Some(format!("{},{}", coord.x, coord.y));
+            }
+        }
+    }
+
+    fn part_2(&self, _input: &str) -> Option<String> {
+        None
+    }
+}
+
 #[test]
 fn test_parsing() {
     let test_data = r"-|/\+><^v";
@@ -1,3 +1,5 @@
+use crate::solution::Solution;
+
 #[derive(Debug)]
 pub struct Kitchen {
     board: Vec<i32>,
@@ -67,6 +69,19 @@ impl Kitchen {
     }
 }
 
+pub struct Solver;
+impl Solution for Solver {
+    fn part_1(&self, input: &str) -> Option<String> {
+        let mut kitchen = Kitchen::new(vec![3, 7], 2);
+        Some(kitchen.score(input.trim()))
+    }
+
+    fn part_2(&self, input: &str) -> Option<String> {
+        let kitchen = Kitchen::new(vec![3, 7], 2);
+        Some(kitchen.appeared_first(input.trim()).to_string())
+    }
+}
+
 #[test]
 fn test_recipe_score() {
     let mut kitchen = Kitchen::new(vec![3, 7], 2);
@@ -1,4 +1,6 @@
 use std::collections::{HashMap, HashSet};
+use itertools::Itertools;
+use crate::solution::{Solution, Trace};
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct Input {
@@ -210,14 +212,15 @@ pub fn exec_test_seq(asm: &Vec<[usize; 4]>, decode_table: HashMap<usize, Operati
 }
 
 pub fn parse_input(raw: &str) -> (Vec<Input>, Vec<[usize; 4]>) {
-    let mut parts = raw.split("\r\n\r\n\r\n\r\n");
+    let raw = raw.replace("\r\n", "\n");
+    let mut parts = raw.split("\n\n\n\n");
     let inputs: Vec<_> =
         parts
         .next()
         .unwrap()
-        .split("\r\n\r\n")
+        .split("\n\n")
         .map(|inp| {
-            let mut parts = inp.split("\r\n");
+            let mut parts = inp.lines();
             let before: Vec<_> =
                 parts
                 .next()
@@ -278,8 +281,36 @@ pub fn parse_input(raw: &str) -> (Vec<Input>, Vec<[usize; 4]>) {
     (inputs, test_sequence)
 }
 
-// fix line endings
-// #[test]
+pub struct Solver;
+impl Solution for Solver {
+    fn part_1(&self, input: &str) -> Option<String> {
+        let (samples, _test_sequence) = parse_input(input);
+        Some(count_three_or_more_compliant(&samples).to_string())
+    }
+
+    fn part_2(&self, input: &str) -> Option<String> {
+        let (samples, test_sequence) = parse_input(input);
+        let memory = exec_test_seq(&test_sequence, decode_operations(&samples));
+        Some(memory[0].to_string())
+    }
+
+    fn explain(&self, input: &str, trace: &mut Trace) {
+        let (samples, test_sequence) = parse_input(input);
+        trace.record("samples", samples.len());
+        trace.record("samples compliant with 3+ operations", count_three_or_more_compliant(&samples));
+
+        let decode_table = decode_operations(&samples);
+        let decoded = trace.section("decoded opcodes");
+        for op_code in decode_table.keys().sorted() {
+            decoded.record(&op_code.to_string(), format!("{:?}", decode_table[op_code]));
+        }
+
+        trace.record("test program length", test_sequence.len());
+        trace.record("final memory", format!("{:?}", exec_test_seq(&test_sequence, decode_table)));
+    }
+}
+
+#[test]
 fn test_op_parsing() {
     let test_input = r"Before: [1, 1, 0, 3]
 3 0 2 0
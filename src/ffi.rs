@@ -0,0 +1,125 @@
+use std::os::raw::c_char;
+use std::panic;
+
+use crate::solution;
+
+/// Answer is written to out_buf.
+pub const ADVENT2018_OK: i32 = 0;
+/// One of the pointers is NULL.
+pub const ADVENT2018_ERR_NULL_POINTER: i32 = 1;
+/// Input is not valid UTF-8.
+pub const ADVENT2018_ERR_INVALID_UTF8: i32 = 2;
+/// Day has no solution.
+pub const ADVENT2018_ERR_UNKNOWN_DAY: i32 = 3;
+/// Part is neither 1 nor 2.
+pub const ADVENT2018_ERR_UNKNOWN_PART: i32 = 4;
+/// Solver gives no answer for this part.
+pub const ADVENT2018_ERR_NOT_SOLVED: i32 = 5;
+/// `*out_len` receives required capacity.
+pub const ADVENT2018_ERR_BUFFER_TOO_SMALL: i32 = 6;
+/// Solver panicked, most likely on malformed input.
+pub const ADVENT2018_ERR_PANIC: i32 = 7;
+
+const VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), "\0");
+
+/// Version of the library as NUL-terminated string with static lifetime.
+#[no_mangle]
+pub extern "C" fn advent2018_version() -> *const c_char {
+    VERSION.as_ptr() as *const c_char
+}
+
+/// Solves `part` (1 or 2) of `day` for `input_len` bytes of UTF-8 input and
+/// writes NUL-terminated answer to `out_buf`. Returns one of the status codes.
+///
+/// # Safety
+///
+/// `input_ptr` must point to `input_len` readable bytes, it may be NULL when
+/// `input_len` is 0. `out_len` must point to capacity of `out_buf`, which in
+/// turn must point to that many writable bytes. On success `*out_len` receives
+/// answer length without the terminating NUL.
+#[no_mangle]
+pub unsafe extern "C" fn advent2018_solve(
+    day: u32,
+    part: u32,
+    input_ptr: *const u8,
+    input_len: usize,
+    out_buf: *mut u8,
+    out_len: *mut usize
+) -> i32 {
+    if (input_ptr.is_null() && input_len > 0) || out_buf.is_null() || out_len.is_null() {
+        return ADVENT2018_ERR_NULL_POINTER;
+    }
+
+    // C callers commonly pass NULL for an empty buffer
+    let input = if input_ptr.is_null() {
+        &[][..]
+    } else {
+        std::slice::from_raw_parts(input_ptr, input_len)
+    };
+    let input = match std::str::from_utf8(input) {
+        Ok(input) => input,
+        Err(_) => return ADVENT2018_ERR_INVALID_UTF8
+    };
+
+    let answer = match panic::catch_unwind(|| solve(day, part, input)) {
+        Ok(Ok(answer)) => answer,
+        Ok(Err(code)) => return code,
+        Err(_) => return ADVENT2018_ERR_PANIC
+    };
+
+    let required = answer.len() + 1;
+    if *out_len < required {
+        *out_len = required;
+        return ADVENT2018_ERR_BUFFER_TOO_SMALL;
+    }
+
+    let out = std::slice::from_raw_parts_mut(out_buf, required);
+    out[..answer.len()].copy_from_slice(answer.as_bytes());
+    out[answer.len()] = 0;
+    *out_len = answer.len();
+
+    ADVENT2018_OK
+}
+
+fn solve(day: u32, part: u32, input: &str) -> Result<String, i32> {
+    if day > 25 {
+        return Err(ADVENT2018_ERR_UNKNOWN_DAY);
+    }
+    let solution = solution::get_solution(day as u8).ok_or(ADVENT2018_ERR_UNKNOWN_DAY)?;
+    let answer = match part {
+        1 => solution.part_1(input),
+        2 => solution.part_2(input),
+        _ => return Err(ADVENT2018_ERR_UNKNOWN_PART)
+    };
+    answer.ok_or(ADVENT2018_ERR_NOT_SOLVED)
+}
+
+#[cfg(test)]
+#[link(name = "advent2018_harness", kind = "static")]
+extern "C" {
+    // defined in ffi/harness.c, returns the amount of failed checks
+    fn advent2018_harness_run() -> i32;
+}
+
+#[test]
+fn test_c_harness() {
+    assert_eq!(unsafe { advent2018_harness_run() }, 0);
+}
+
+// build script generates the header from this module and compiles the harness
+// against it, the shipped copy has to stay the same
+#[test]
+fn test_header_is_up_to_date() {
+    let shipped = include_str!("../ffi/advent2018.h").replace("\r\n", "\n");
+    let generated = include_str!(concat!(env!("OUT_DIR"), "/advent2018.h"));
+    assert_eq!(shipped, generated, "copy generated advent2018.h from OUT_DIR to ffi/");
+}
+
+#[test]
+fn test_solve() {
+    assert_eq!(solve(1, 1, "+1\n-2\n+3\n+1"), Ok(String::from("3")));
+    assert_eq!(solve(1, 3, "+1"), Err(ADVENT2018_ERR_UNKNOWN_PART));
+    assert_eq!(solve(1, 2, "+2\n+3\n-1"), Err(ADVENT2018_ERR_NOT_SOLVED));
+    assert_eq!(solve(12, 1, ""), Err(ADVENT2018_ERR_UNKNOWN_DAY));
+    assert_eq!(solve(300, 1, ""), Err(ADVENT2018_ERR_UNKNOWN_DAY));
+}
@@ -0,0 +1,24 @@
+#![allow(dead_code)]
+// #![allow(unused_variables)]
+
+pub mod day01;
+pub mod day02;
+pub mod day03;
+pub mod day04;
+pub mod day05;
+pub mod day06;
+pub mod day07;
+pub mod day08;
+pub mod day09;
+pub mod day10;
+pub mod day11;
+pub mod day12;
+pub mod day13;
+pub mod day14;
+pub mod day15;
+pub mod day16;
+
+mod freestyle;
+
+pub mod ffi;
+pub mod solution;
@@ -0,0 +1,111 @@
+use std::fmt;
+
+use crate::{
+    day01, day02, day03, day04, day05, day06, day07,
+    day08, day09, day10, day11, day13, day14, day16
+};
+
+// common face of every solved day, so the runner doesn't need to know
+// how each puzzle wires its parsers and structures together
+pub trait Solution {
+    fn part_1(&self, input: &str) -> Option<String>;
+
+    fn part_2(&self, input: &str) -> Option<String>;
+
+    // records key intermediate results behind the answers,
+    // solvers which don't opt in leave the trace empty
+    fn explain(&self, _input: &str, _trace: &mut Trace) {}
+}
+
+pub fn get_solution(day: u8) -> Option<Box<dyn Solution>> {
+    let solution: Box<dyn Solution> = match day {
+        1 => Box::new(day01::Solver),
+        2 => Box::new(day02::Solver),
+        3 => Box::new(day03::Solver),
+        4 => Box::new(day04::Solver),
+        5 => Box::new(day05::Solver),
+        6 => Box::new(day06::Solver),
+        7 => Box::new(day07::Solver),
+        8 => Box::new(day08::Solver),
+        9 => Box::new(day09::Solver),
+        10 => Box::new(day10::Solver),
+        11 => Box::new(day11::Solver),
+        13 => Box::new(day13::Solver),
+        14 => Box::new(day14::Solver),
+        16 => Box::new(day16::Solver),
+        _ => return None
+    };
+    Some(solution)
+}
+
+#[derive(Debug, PartialEq)]
+enum Entry {
+    Value(String, String),
+    Section(String, Trace)
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Trace {
+    entries: Vec<Entry>
+}
+impl Trace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record<T: fmt::Display>(&mut self, label: &str, value: T) {
+        self.entries.push(Entry::Value(label.to_string(), value.to_string()));
+    }
+
+    // opens nested trace which is printed one level deeper
+    pub fn section(&mut self, label: &str) -> &mut Trace {
+        self.entries.push(Entry::Section(label.to_string(), Trace::new()));
+        match self.entries.last_mut() {
+            Some(Entry::Section(_, trace)) => trace,
+            _ => unreachable!()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn write_indented(&self, f: &mut fmt::Formatter, depth: usize) -> fmt::Result {
+        let indent = "  ".repeat(depth);
+        for entry in &self.entries {
+            match entry {
+                Entry::Value(label, value) => writeln!(f, "{}{}: {}", indent, label, value)?,
+                Entry::Section(label, trace) => {
+                    writeln!(f, "{}{}:", indent, label)?;
+                    trace.write_indented(f, depth + 1)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_indented(f, 0)
+    }
+}
+
+#[test]
+fn test_trace_report() {
+    let mut trace = Trace::new();
+    trace.record("answer", 42);
+    let section = trace.section("details");
+    section.record("guard", 10);
+    section.section("minutes").record("24", 2);
+
+    let expected = "answer: 42\ndetails:\n  guard: 10\n  minutes:\n    24: 2\n";
+
+    assert_eq!(trace.to_string(), expected);
+}
+
+#[test]
+fn test_registry() {
+    assert!(get_solution(1).is_some());
+    assert!(get_solution(12).is_none());
+    assert!(get_solution(26).is_none());
+}